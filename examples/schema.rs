@@ -3,7 +3,11 @@ use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
-use cw20_clawback::msg::{DetailsResponse, HandleMsg, InitMsg, ListResponse, QueryMsg, ReceiveMsg};
+use cw20_clawback::msg::{
+    ConfigResponse, CreateResponse, DetailsResponse, ExpiringResponse, HandleMsg, InitMsg,
+    ListDetailsResponse, ListProposalsResponse, ListResponse, MigrateMsg, ProposalResponse,
+    QueryMsg, ReceiveMsg, TotalLockedResponse,
+};
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -13,8 +17,16 @@ fn main() {
 
     export_schema(&schema_for!(InitMsg), &out_dir);
     export_schema(&schema_for!(HandleMsg), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
     export_schema(&schema_for!(ReceiveMsg), &out_dir);
+    export_schema(&schema_for!(CreateResponse), &out_dir);
     export_schema(&schema_for!(DetailsResponse), &out_dir);
     export_schema(&schema_for!(ListResponse), &out_dir);
+    export_schema(&schema_for!(ListDetailsResponse), &out_dir);
+    export_schema(&schema_for!(ExpiringResponse), &out_dir);
+    export_schema(&schema_for!(ProposalResponse), &out_dir);
+    export_schema(&schema_for!(ListProposalsResponse), &out_dir);
+    export_schema(&schema_for!(TotalLockedResponse), &out_dir);
+    export_schema(&schema_for!(ConfigResponse), &out_dir);
 }
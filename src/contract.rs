@@ -1,16 +1,29 @@
 use cosmwasm_std::{
-    attr, from_binary, to_binary, Api, BankMsg, Binary, CosmosMsg, Env, Extern, HandleResponse,
-    HumanAddr, InitResponse, MessageInfo, Querier, StdResult, Storage, WasmMsg,
+    attr, from_binary, to_binary, Api, Attribute, BankMsg, Binary, CanonicalAddr, CosmosMsg, Env,
+    Extern, HandleResponse, HumanAddr, InitResponse, MessageInfo, MigrateResponse, Querier,
+    StdError, StdResult, Storage, WasmMsg,
 };
 
-use cw2::set_contract_version;
+use cw0::{Duration, Expiration};
+use cw2::{get_contract_version, set_contract_version};
 use cw20::{Balance, Cw20Coin, Cw20CoinHuman, Cw20HandleMsg, Cw20ReceiveMsg};
 
 use crate::error::ContractError;
 use crate::msg::{
-    CreateMsg, DetailsResponse, HandleMsg, InitMsg, ListResponse, QueryMsg, ReceiveMsg,
+    is_valid_name, BackupAction, ClawbackLimit, ConfigResponse, CreateMsg, CreateResponse,
+    DepositPolicy, DetailsResponse, Dispute, DisputeOutcome, EmergencyPolicy, ExpiringClawback,
+    ExpiringResponse, HandleMsg, InitMsg, ListDetailsResponse, ListProposalsResponse, ListResponse,
+    MigrateMsg, Multisig, ProposalResponse, QueryMsg, ReceiveMsg, RefreshPolicy, ReleaseSchedule,
+    TokenAllowlist, TotalLockedResponse, TransferCreateMsg, TransferTerms, UnlistedDenoms,
+};
+use crate::state::{
+    address_index_ids, clawback_ids, clawback_proposal_ids, clawbacks_read, config, config_read,
+    expiry_index_range, expiry_key, expiry_prefix, migrate_clawbacks_v0_3, migration_cursor,
+    migration_cursor_read, next_clawback_id, next_proposal_id, proposals_read, range_clawbacks,
+    range_proposals, remove_clawback, remove_proposal, save_clawback, save_proposal, total_locked,
+    total_locked_read, Allowlist, BackupMultisig, Clawback, Config, Depositors, GenericBalance,
+    Proposal, PREFIX_BACKUP_INDEX, PREFIX_HOLDER_INDEX,
 };
-use crate::state::{all_clawback_ids, clawbacks, clawbacks_read, Clawback, GenericBalance};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw20-clawback";
@@ -20,10 +33,27 @@ pub fn init<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     _env: Env,
     _info: MessageInfo,
-    _msg: InitMsg,
+    msg: InitMsg,
 ) -> StdResult<InitResponse> {
     set_contract_version(&mut deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    // no setup
+    let native_sink = match msg.native_sink {
+        Some(sink) => Some(deps.api.canonical_address(&sink)?),
+        None => None,
+    };
+    let admin = match msg.admin {
+        Some(admin) => Some(deps.api.canonical_address(&admin)?),
+        None => None,
+    };
+    let cfg = Config {
+        native_sink,
+        admin,
+        paused: false,
+        emergency_policy: msg.emergency_policy.unwrap_or_default(),
+        allowlist: canonical_allowlist(&deps.api, msg.allowlist)?,
+        max_tokens: msg.max_tokens,
+        peers: canonical_peers(&deps.api, &msg.peers.unwrap_or_default())?,
+    };
+    config(&mut deps.storage).save(&cfg)?;
     Ok(InitResponse::default())
 }
 
@@ -33,18 +63,68 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
     info: MessageInfo,
     msg: HandleMsg,
 ) -> Result<HandleResponse, ContractError> {
+    // not all clawbacks can be read before the migration is done
+    if migration_cursor_read(&deps.storage).may_load()?.is_some() {
+        return Err(ContractError::MigrationPending {});
+    }
     match msg {
-        HandleMsg::Create(msg) => try_create(deps, env, msg, Balance::from(info.sent_funds)),
-        HandleMsg::TopUp { id } => try_top_up(deps, id, Balance::from(info.sent_funds)),
+        HandleMsg::Create(msg) => {
+            try_create(deps, env, info.sender, msg, Balance::from(info.sent_funds))
+        }
+        HandleMsg::TopUp { id } => {
+            try_top_up(deps, env, info.sender, id, Balance::from(info.sent_funds))
+        }
         HandleMsg::Receive(msg) => try_receive(deps, env, info, msg),
-        HandleMsg::Withdraw { id } => try_withdraw(deps, env, info, id),
+        HandleMsg::Withdraw { id, recipient, msg } => {
+            try_withdraw(deps, env, info, id, recipient, msg)
+        }
+        HandleMsg::WithdrawPartial {
+            id,
+            amount,
+            recipient,
+            msg,
+        } => try_withdraw_partial(deps, env, info, id, amount, recipient, msg),
         HandleMsg::Refresh { id } => try_refresh(deps, env, info, id),
         HandleMsg::Burn { id } => try_burn(deps, env, info, id),
+        HandleMsg::Clawback {
+            id,
+            recipient,
+            amount,
+        } => try_clawback(deps, env, info, id, recipient, amount),
         HandleMsg::ClawbackTransfer {
             from_id,
             to_id,
             amount,
-        } => try_transfer(deps, env, info, from_id, to_id, amount),
+            create,
+        } => try_transfer(deps, env, info, from_id, to_id, amount, create),
+        HandleMsg::TransferRemote {
+            from_id,
+            contract,
+            to_id,
+            amount,
+        } => try_transfer_remote(deps, env, info, from_id, contract, to_id, amount),
+        HandleMsg::TransferIn { id, terms } => try_transfer_in(
+            deps,
+            env,
+            info.sender,
+            id,
+            terms,
+            Balance::from(info.sent_funds),
+        ),
+        HandleMsg::UpdateHolder { id, holder } => try_update_holder(deps, env, info, id, holder),
+        HandleMsg::UpdateBackup { id, backup } => try_update_backup(deps, env, info, id, backup),
+        HandleMsg::AcceptBackup { id } => try_accept_backup(deps, info, id),
+        HandleMsg::Propose { id, action } => try_propose(deps, env, info, id, action),
+        HandleMsg::Vote { proposal_id } => try_vote(deps, env, info, proposal_id),
+        HandleMsg::Dispute { id, reason } => try_dispute(deps, env, info, id, reason),
+        HandleMsg::Resolve { id, outcome } => try_resolve(deps, env, info, id, outcome),
+        HandleMsg::Pause { emergency_policy } => try_pause(deps, info, emergency_policy),
+        HandleMsg::Unpause {} => try_unpause(deps, info),
+        HandleMsg::UpdateAllowlist {
+            allowlist,
+            max_tokens,
+        } => try_update_allowlist(deps, info, allowlist, max_tokens),
+        HandleMsg::UpdatePeers { peers } => try_update_peers(deps, info, peers),
     }
 }
 
@@ -53,30 +133,114 @@ pub fn try_withdraw<S: Storage, A: Api, Q: Querier>(
     env: Env,
     info: MessageInfo,
     id: String,
+    recipient: Option<HumanAddr>,
+    msg: Option<Binary>,
 ) -> Result<HandleResponse, ContractError> {
     // this fails is no clawback there
-    let clawback = clawbacks_read(&deps.storage).load(id.as_bytes())?;
+    let mut clawback = clawbacks_read(&deps.storage).load(id.as_bytes())?;
 
     if deps.api.canonical_address(&info.sender)? != clawback.holder {
-        Err(ContractError::Unauthorized {})
-    } else if !clawback.is_expired(&env) {
-        Err(ContractError::NotExpired {})
-    } else {
+        return Err(ContractError::Unauthorized {});
+    }
+    assert_not_paused(&deps.storage, |policy| policy.holder_withdraw)?;
+    let amount = if clawback.is_expired(&env) {
         // we delete the clawback
-        clawbacks(&mut deps.storage).remove(id.as_bytes());
+        remove_clawback(&mut deps.storage, id.as_bytes())?;
+        clawback.balance
+    } else {
+        // only the released part
+        let amount = clawback.withdrawable(&env);
+        if amount.is_empty() {
+            return Err(ContractError::NotExpired {});
+        }
+        clawback
+            .balance
+            .remove_balance(&amount)
+            .map_err(|_| ContractError::NotEnoughBalance {})?;
+        clawback.record_withdrawal(&amount);
+        if clawback.balance.is_empty() {
+            remove_clawback(&mut deps.storage, id.as_bytes())?;
+        } else {
+            save_clawback(&mut deps.storage, id.as_bytes(), &clawback)?;
+        }
+        amount
+    };
 
-        let rcpt = deps.api.human_address(&clawback.holder)?;
+    let rcpt = match recipient {
+        Some(recipient) => recipient,
+        None => deps.api.human_address(&clawback.holder)?,
+    };
 
-        // send all tokens out
-        let messages = send_tokens(&deps.api, &env.contract.address, &rcpt, &clawback.balance)?;
+    // send the tokens out
+    let messages = send_tokens(&deps.api, &env.contract.address, &rcpt, &amount, msg)?;
 
-        let attributes = vec![attr("action", "withdraw"), attr("id", id), attr("to", rcpt)];
-        Ok(HandleResponse {
-            messages,
-            attributes,
-            data: None,
-        })
+    let attributes = vec![attr("action", "withdraw"), attr("id", id), attr("to", rcpt)];
+    Ok(HandleResponse {
+        messages,
+        attributes,
+        data: None,
+    })
+}
+
+pub fn try_withdraw_partial<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+    mut amount: Balance,
+    recipient: HumanAddr,
+    msg: Option<Binary>,
+) -> Result<HandleResponse, ContractError> {
+    amount.normalize();
+    if amount.is_empty() {
+        return Err(ContractError::EmptyBalance {});
+    }
+    // this fails is no clawback there
+    let mut clawback = clawbacks_read(&deps.storage).load(id.as_bytes())?;
+
+    if deps.api.canonical_address(&info.sender)? != clawback.holder {
+        return Err(ContractError::Unauthorized {});
+    }
+    assert_not_paused(&deps.storage, |policy| policy.holder_withdraw)?;
+    let expired = clawback.is_expired(&env);
+    let mut released = clawback.withdrawable(&env);
+    clawback
+        .balance
+        .remove_tokens(amount.clone())
+        .map_err(|_| ContractError::NotEnoughBalance {})?;
+    if !expired {
+        // only out of the released part
+        released
+            .remove_tokens(amount.clone())
+            .map_err(|_| ContractError::NotExpired {})?;
+        clawback.record_withdrawal(&GenericBalance::from(amount.clone()));
+    }
+    if clawback.balance.is_empty() {
+        // nothing left, we delete the clawback
+        remove_clawback(&mut deps.storage, id.as_bytes())?;
+    } else {
+        save_clawback(&mut deps.storage, id.as_bytes(), &clawback)?;
     }
+
+    // send the requested tokens out
+    let messages = send_tokens(
+        &deps.api,
+        &env.contract.address,
+        &recipient,
+        &GenericBalance::from(amount),
+        msg,
+    )?;
+
+    let attributes = vec![
+        attr("action", "withdraw_partial"),
+        attr("id", id),
+        attr("to", recipient),
+    ];
+    Ok(HandleResponse {
+        messages,
+        attributes,
+        data: None,
+    })
 }
 
 pub fn try_refresh<S: Storage, A: Api, Q: Querier>(
@@ -86,20 +250,51 @@ pub fn try_refresh<S: Storage, A: Api, Q: Querier>(
     id: String,
 ) -> Result<HandleResponse, ContractError> {
     // this fails is no clawback there
-    let mut clawback = clawbacks_read(&deps.storage).load(id.as_bytes())?;
+    let clawback = clawbacks_read(&deps.storage).load(id.as_bytes())?;
     let sender = deps.api.canonical_address(&info.sender)?;
 
-    if sender != clawback.holder && !(!clawback.is_expired(&env) && sender == clawback.backup) {
-        Err(ContractError::Unauthorized {})
+    let policy = &clawback.refresh_policy;
+    let allowed = if sender == clawback.holder {
+        clawback.holder_may_refresh(&env)
+    } else if !clawback.is_expired(&env) && clawback.is_sole_backup(&sender) {
+        if clawback.is_disputed() {
+            return Err(ContractError::Disputed {});
+        }
+        policy.refresh_by.backup()
     } else {
-        clawback.end_time = env.block.time + clawback.clawback_period;
-        // try to store it
-        clawbacks(&mut deps.storage).save(id.as_bytes(), &clawback)?;
+        return Err(ContractError::Unauthorized {});
+    };
+    if !allowed {
+        return Err(ContractError::RefreshNotAllowed {});
+    }
+    // the holder locks the released tokens again as well
+    let relock = sender == clawback.holder;
+    execute_refresh(deps, &env, id, clawback, relock)
+}
 
-        let mut res = HandleResponse::default();
-        res.attributes = vec![attr("action", "refresh"), attr("id", id)];
-        Ok(res)
+fn execute_refresh<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    id: String,
+    mut clawback: Clawback,
+    relock: bool,
+) -> Result<HandleResponse, ContractError> {
+    if !clawback.can_refresh(env) {
+        return Err(ContractError::RefreshNotAllowed {});
+    }
+    if relock {
+        clawback.relock(env);
+    } else {
+        clawback.refresh(env);
     }
+    // try to store it
+    save_clawback(&mut deps.storage, id.as_bytes(), &clawback)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        attributes: vec![attr("action", "refresh"), attr("id", id)],
+        data: None,
+    })
 }
 
 pub fn try_burn<S: Storage, A: Api, Q: Querier>(
@@ -112,17 +307,123 @@ pub fn try_burn<S: Storage, A: Api, Q: Querier>(
     let clawback = clawbacks_read(&deps.storage).load(id.as_bytes())?;
     let sender = deps.api.canonical_address(&info.sender)?;
 
-    if clawback.is_expired(&env) || sender != clawback.backup {
+    if clawback.is_expired(&env) || !clawback.is_sole_backup(&sender) {
         Err(ContractError::Unauthorized {})
+    } else if clawback.is_disputed() {
+        Err(ContractError::Disputed {})
     } else {
-        // we delete the clawback
-        clawbacks(&mut deps.storage).remove(id.as_bytes());
+        execute_burn(deps, &env, id, clawback)
+    }
+}
+
+fn execute_burn<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    id: String,
+    mut clawback: Clawback,
+) -> Result<HandleResponse, ContractError> {
+    assert_not_paused(&deps.storage, |policy| policy.backup_clawback)?;
+    // the released part stays with the holder
+    let burned = clawback.unreleased(env);
+    if burned.is_empty() {
+        return Err(ContractError::EmptyBalance {});
+    }
+    take_back(env, &mut clawback, &burned)?;
+    clawback
+        .balance
+        .remove_balance(&burned)
+        .map_err(|_| ContractError::NotEnoughBalance {})?;
+    let native_sink = match config_read(&deps.storage).load()?.native_sink {
+        Some(sink) => Some(deps.api.human_address(&sink)?),
+        None => None,
+    };
+    // destroy the tokens
+    let messages = burn_tokens(
+        &deps.api,
+        &env.contract.address,
+        native_sink.as_ref(),
+        &burned,
+    )?;
+
+    if clawback.balance.is_empty() {
+        // nothing left, we delete the clawback
+        remove_clawback(&mut deps.storage, id.as_bytes())?;
+    } else {
+        save_clawback(&mut deps.storage, id.as_bytes(), &clawback)?;
+    }
+
+    Ok(HandleResponse {
+        messages,
+        attributes: vec![attr("action", "burn"), attr("id", id)],
+        data: None,
+    })
+}
+
+pub fn try_clawback<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+    recipient: HumanAddr,
+    amount: Option<Balance>,
+) -> Result<HandleResponse, ContractError> {
+    // this fails is no clawback there
+    let clawback = clawbacks_read(&deps.storage).load(id.as_bytes())?;
+    let sender = deps.api.canonical_address(&info.sender)?;
+
+    if clawback.is_expired(&env) || !clawback.is_sole_backup(&sender) {
+        Err(ContractError::Unauthorized {})
+    } else if clawback.is_disputed() {
+        Err(ContractError::Disputed {})
+    } else {
+        execute_clawback(deps, &env, id, clawback, recipient, amount)
+    }
+}
 
-        // FIXME: some bank/supply burn messages to be returned?
-        let mut res = HandleResponse::default();
-        res.attributes = vec![attr("action", "burn"), attr("id", id)];
-        Ok(res)
+fn execute_clawback<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    id: String,
+    mut clawback: Clawback,
+    recipient: HumanAddr,
+    amount: Option<Balance>,
+) -> Result<HandleResponse, ContractError> {
+    assert_not_paused(&deps.storage, |policy| policy.backup_clawback)?;
+    // the released part stays with the holder
+    let taken = match amount {
+        Some(mut amount) => {
+            amount.normalize();
+            GenericBalance::from(amount)
+        }
+        None => clawback.unreleased(env),
+    };
+    if taken.is_empty() {
+        return Err(ContractError::EmptyBalance {});
+    }
+    take_back(env, &mut clawback, &taken)?;
+    clawback
+        .balance
+        .remove_balance(&taken)
+        .map_err(|_| ContractError::NotEnoughBalance {})?;
+    if clawback.balance.is_empty() {
+        // nothing left, we delete the clawback
+        remove_clawback(&mut deps.storage, id.as_bytes())?;
+    } else {
+        save_clawback(&mut deps.storage, id.as_bytes(), &clawback)?;
     }
+
+    // send the tokens to the recovery address
+    let messages = send_tokens(&deps.api, &env.contract.address, &recipient, &taken, None)?;
+
+    Ok(HandleResponse {
+        messages,
+        attributes: vec![
+            attr("action", "clawback"),
+            attr("id", id),
+            attr("to", recipient),
+        ],
+        data: None,
+    })
 }
 
 pub fn try_transfer<S: Storage, A: Api, Q: Querier>(
@@ -132,1203 +433,6031 @@ pub fn try_transfer<S: Storage, A: Api, Q: Querier>(
     from_id: String,
     to_id: String,
     amount: Balance,
+    create: Option<TransferCreateMsg>,
 ) -> Result<HandleResponse, ContractError> {
     if amount.is_empty() {
         return Err(ContractError::EmptyBalance {});
     }
     let mut clawback_from = clawbacks_read(&deps.storage).load(from_id.as_bytes())?;
-    let mut clawback_to = clawbacks_read(&deps.storage).load(to_id.as_bytes())?;
-    if clawback_from.backup != clawback_to.backup
-        || clawback_from.clawback_period > clawback_to.clawback_period
-    {
-        Err(ContractError::ContractMismatch {})
-    } else {
-        let sender = deps.api.canonical_address(&info.sender)?;
-        if !(!clawback_from.is_expired(&env) && sender == clawback_from.backup)
-            && sender != clawback_from.holder
-        {
+    let sender = deps.api.canonical_address(&info.sender)?;
+    if sender != clawback_from.holder {
+        if clawback_from.is_expired(&env) || !clawback_from.is_sole_backup(&sender) {
             return Err(ContractError::Unauthorized {});
         }
+        if clawback_from.is_disputed() {
+            return Err(ContractError::Disputed {});
+        }
+        // the backup takes the tokens away from the holder
+        take_back(
+            &env,
+            &mut clawback_from,
+            &GenericBalance::from(amount.clone()),
+        )?;
+    } else {
+        // the holder moves the released tokens first
+        clawback_from
+            .released
+            .remove_up_to(&GenericBalance::from(amount.clone()));
+    }
+    execute_transfer(
+        deps,
+        &env,
+        &sender,
+        from_id,
+        clawback_from,
+        to_id,
+        amount,
+        create,
+    )
+}
 
-        for address in clawback_from.cw20_whitelist.iter() {
-            if !clawback_to.cw20_whitelist.iter().any(|t| t == address) {
-                return Err(ContractError::NotInWhitelist {});
+#[allow(clippy::too_many_arguments)]
+fn execute_transfer<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    sender: &CanonicalAddr,
+    from_id: String,
+    mut clawback_from: Clawback,
+    to_id: String,
+    amount: Balance,
+    create: Option<TransferCreateMsg>,
+) -> Result<HandleResponse, ContractError> {
+    assert_not_paused(&deps.storage, |_| false)?;
+    // the balance of the receiving clawback would overwrite the outgoing one
+    if from_id == to_id {
+        return Err(ContractError::ContractMismatch {});
+    }
+    let existing = clawbacks_read(&deps.storage).may_load(to_id.as_bytes())?;
+    let created = existing.is_none();
+    let mut clawback_to = match (existing, create) {
+        (Some(_), Some(_)) => return Err(ContractError::AlreadyInUse {}),
+        (Some(clawback), None) => clawback,
+        (None, Some(create)) => {
+            if !is_valid_name(&to_id) {
+                return Err(ContractError::InvalidId {});
+            }
+            // the new clawback is under the same backup authority and refresh policy
+            let refresh_policy = clawback_from.refresh_policy.clone();
+            Clawback {
+                backup: clawback_from.backup.clone(),
+                holder: deps.api.canonical_address(&create.holder)?,
+                clawback_period: create.clawback_period,
+                end_time: create.clawback_period.after(&env.block),
+                balance: GenericBalance::default(),
+                cw20_whitelist: clawback_from.cw20_whitelist.clone(),
+                backup_multisig: clawback_from.backup_multisig.clone(),
+                pending_backup: None,
+                max_end_time: max_end_time(env, &create.clawback_period, &refresh_policy)?,
+                refresh_policy,
+                refreshes: 0,
+                release: clawback_from.release.clone(),
+                withdrawn: GenericBalance::default(),
+                taken_back: GenericBalance::default(),
+                released: GenericBalance::default(),
+                max_clawback: clawback_from.max_clawback.clone(),
+                clawed_back: GenericBalance::default(),
+                arbiter: clawback_from.arbiter.clone(),
+                dispute: None,
+                depositors: clawback_from.depositors.clone(),
+                native_whitelist: clawback_from.native_whitelist.clone(),
+                unlisted_denoms: clawback_from.unlisted_denoms.clone(),
             }
         }
-        clawback_from
-            .balance
-            .remove_tokens(amount.clone())
-            .map_err(|_| ContractError::NotEnoughBalance {})?;
-        clawback_to.balance.add_tokens(amount);
-        clawback_to.end_time = env.block.time + clawback_to.clawback_period;
-        // try to store it
-        let mut bucket = clawbacks(&mut deps.storage);
-        bucket.save(from_id.as_bytes(), &clawback_from)?;
-        bucket.save(to_id.as_bytes(), &clawback_to)?;
-        let mut res = HandleResponse::default();
-        res.attributes = vec![
+        // this fails as there is no clawback
+        (None, None) => clawbacks_read(&deps.storage).load(to_id.as_bytes())?,
+    };
+    if !clawback_from.same_backup(&clawback_to)
+        || !clawback_to.covers_period(&clawback_from.clawback_period)
+    {
+        return Err(ContractError::ContractMismatch {});
+    }
+
+    // the receiving clawback must take all tokens the outgoing one may hold,
+    // and in particular the transferred ones
+    if !clawback_to.covers_whitelists(
+        &clawback_from.cw20_whitelist,
+        &clawback_from.native_whitelist,
+    ) || !clawback_to.accepts(&amount)
+    {
+        return Err(ContractError::NotInWhitelist {});
+    }
+    clawback_from
+        .balance
+        .remove_tokens(amount.clone())
+        .map_err(|_| ContractError::NotEnoughBalance {})?;
+    // an existing clawback only takes tokens from its depositors
+    if !created && !clawback_to.may_deposit(sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    // a new clawback just started its period
+    if !created {
+        if !clawback_to.may_refresh_by_transfer(Some(sender)) || !clawback_to.can_refresh(env) {
+            return Err(ContractError::RefreshNotAllowed {});
+        }
+        clawback_to.refresh(env);
+    }
+    // the transferred tokens are released over the new period
+    clawback_to.balance.add_tokens(amount);
+    assert_max_tokens(&deps.storage, &clawback_to.balance)?;
+    // try to store it
+    save_clawback(&mut deps.storage, from_id.as_bytes(), &clawback_from)?;
+    save_clawback(&mut deps.storage, to_id.as_bytes(), &clawback_to)?;
+    Ok(HandleResponse {
+        messages: vec![],
+        attributes: vec![
             attr("action", "transfer"),
             attr("from_id", from_id),
             attr("to_id", to_id),
-        ];
-        Ok(res)
-    }
+        ],
+        data: None,
+    })
 }
 
-pub fn try_receive<S: Storage, A: Api, Q: Querier>(
+pub fn try_update_holder<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
     info: MessageInfo,
-    wrapper: Cw20ReceiveMsg,
+    id: String,
+    holder: HumanAddr,
 ) -> Result<HandleResponse, ContractError> {
-    let msg: ReceiveMsg = match wrapper.msg {
-        Some(bin) => Ok(from_binary(&bin)?),
-        None => Err(ContractError::NoData {}),
-    }?;
-    let balance = Balance::Cw20(Cw20Coin {
-        address: deps.api.canonical_address(&info.sender)?,
-        amount: wrapper.amount,
-    });
-    match msg {
-        ReceiveMsg::Create(msg) => try_create(deps, env, msg, balance),
-        ReceiveMsg::TopUp { id } => try_top_up(deps, id, balance),
+    // this fails is no clawback there
+    let mut clawback = clawbacks_read(&deps.storage).load(id.as_bytes())?;
+    let sender = deps.api.canonical_address(&info.sender)?;
+
+    if sender == clawback.holder {
+        // the new holder gets a full clawback period, if the refresh policy
+        // lets the holder refresh (otherwise the period goes on)
+        if clawback.holder_may_refresh(&env) && clawback.can_refresh(&env) {
+            clawback.relock(&env);
+        }
+        execute_update_holder(deps, id, clawback, holder)
+    } else if !clawback.is_expired(&env) && clawback.is_sole_backup(&sender) {
+        if clawback.is_disputed() {
+            return Err(ContractError::Disputed {});
+        }
+        record_holder_change(&mut clawback)?;
+        execute_update_holder(deps, id, clawback, holder)
+    } else {
+        Err(ContractError::Unauthorized {})
     }
 }
 
-pub fn try_create<S: Storage, A: Api, Q: Querier>(
+/// Records tokens the backup takes away from the holder, which must not be released yet
+/// and stay within the max_clawback
+fn take_back(
+    env: &Env,
+    clawback: &mut Clawback,
+    amount: &GenericBalance,
+) -> Result<(), ContractError> {
+    clawback
+        .unreleased(env)
+        .remove_balance(amount)
+        .map_err(|_| ContractError::NotEnoughBalance {})?;
+    clawback
+        .record_clawback(amount)
+        .map_err(|_| ContractError::ClawbackLimitExceeded {})?;
+    clawback.taken_back.add_balance(amount);
+    Ok(())
+}
+
+/// With a max_clawback, the backup could take all tokens by making itself the holder,
+/// so a holder change by the backup counts the whole balance against it
+fn record_holder_change(clawback: &mut Clawback) -> Result<(), ContractError> {
+    if clawback.max_clawback.is_some() {
+        clawback
+            .record_clawback(&clawback.balance.clone())
+            .map_err(|_| ContractError::ClawbackLimitExceeded {})?;
+    }
+    Ok(())
+}
+
+fn execute_update_holder<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
-    env: Env,
-    msg: CreateMsg,
-    balance: Balance,
+    id: String,
+    mut clawback: Clawback,
+    holder: HumanAddr,
 ) -> Result<HandleResponse, ContractError> {
-    if balance.is_empty() {
-        return Err(ContractError::EmptyBalance {});
-    }
+    let old_holder = deps.api.human_address(&clawback.holder)?;
+    clawback.holder = deps.api.canonical_address(&holder)?;
+    // this also moves it in the holder index
+    save_clawback(&mut deps.storage, id.as_bytes(), &clawback)?;
 
-    let mut cw20_whitelist = msg.canonical_whitelist(&deps.api)?;
+    Ok(HandleResponse {
+        messages: vec![],
+        attributes: vec![
+            attr("action", "update_holder"),
+            attr("id", id),
+            attr("from", old_holder),
+            attr("to", holder),
+        ],
+        data: None,
+    })
+}
 
-    let clawback_balance = match balance {
-        Balance::Native(balance) => GenericBalance {
-            native: balance.0,
-            cw20: vec![],
-        },
-        Balance::Cw20(token) => {
-            // make sure the token sent is on the whitelist by default
-            if !cw20_whitelist.iter().any(|t| t == &token.address) {
-                cw20_whitelist.push(token.address.clone())
-            }
-            GenericBalance {
-                native: vec![],
-                cw20: vec![token],
-            }
-        }
-    };
+pub fn try_update_backup<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+    backup: HumanAddr,
+) -> Result<HandleResponse, ContractError> {
+    // this fails is no clawback there
+    let clawback = clawbacks_read(&deps.storage).load(id.as_bytes())?;
+    let sender = deps.api.canonical_address(&info.sender)?;
 
-    let clawback = Clawback {
-        backup: deps.api.canonical_address(&msg.backup)?,
-        holder: deps.api.canonical_address(&msg.holder)?,
-        clawback_period: msg.clawback_period,
-        end_time: env.block.time + msg.clawback_period,
-        balance: clawback_balance,
-        cw20_whitelist,
-    };
+    if clawback.is_expired(&env) || !clawback.is_sole_backup(&sender) {
+        Err(ContractError::Unauthorized {})
+    } else if clawback.is_disputed() {
+        Err(ContractError::Disputed {})
+    } else {
+        execute_update_backup(deps, id, clawback, backup)
+    }
+}
 
-    // try to store it, fail if the id was already in use
-    clawbacks(&mut deps.storage).update(msg.id.as_bytes(), |existing| match existing {
-        None => Ok(clawback),
-        Some(_) => Err(ContractError::AlreadyInUse {}),
-    })?;
+fn execute_update_backup<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    id: String,
+    mut clawback: Clawback,
+    backup: HumanAddr,
+) -> Result<HandleResponse, ContractError> {
+    let old_backup = deps.api.human_address(&clawback.backup)?;
+    clawback.pending_backup = Some(deps.api.canonical_address(&backup)?);
+    save_clawback(&mut deps.storage, id.as_bytes(), &clawback)?;
 
-    let mut res = HandleResponse::default();
-    res.attributes = vec![attr("action", "create"), attr("id", msg.id)];
-    Ok(res)
+    Ok(HandleResponse {
+        messages: vec![],
+        attributes: vec![
+            attr("action", "update_backup"),
+            attr("id", id),
+            attr("from", old_backup),
+            attr("to", backup),
+        ],
+        data: None,
+    })
 }
 
-pub fn try_top_up<S: Storage, A: Api, Q: Querier>(
+pub fn try_accept_backup<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
+    info: MessageInfo,
     id: String,
-    balance: Balance,
 ) -> Result<HandleResponse, ContractError> {
-    if balance.is_empty() {
-        return Err(ContractError::EmptyBalance {});
-    }
     // this fails is no clawback there
     let mut clawback = clawbacks_read(&deps.storage).load(id.as_bytes())?;
+    let sender = deps.api.canonical_address(&info.sender)?;
 
-    if let Balance::Cw20(token) = &balance {
-        // ensure the token is on the whitelist
-        if !clawback.cw20_whitelist.iter().any(|t| t == &token.address) {
-            return Err(ContractError::NotInWhitelist {});
-        }
-    };
-
-    clawback.balance.add_tokens(balance);
-
-    // and save
-    clawbacks(&mut deps.storage).save(id.as_bytes(), &clawback)?;
+    if clawback.pending_backup.as_ref() != Some(&sender) {
+        Err(ContractError::Unauthorized {})
+    } else if clawback.is_disputed() {
+        Err(ContractError::Disputed {})
+    } else {
+        let old_backup = deps.api.human_address(&clawback.backup)?;
+        clawback.backup = sender;
+        clawback.pending_backup = None;
+        // the new backup acts alone, also when a multisig handed the role over
+        clawback.backup_multisig = None;
+        // this also moves it in the backup index
+        save_clawback(&mut deps.storage, id.as_bytes(), &clawback)?;
 
-    let mut res = HandleResponse::default();
-    res.attributes = vec![attr("action", "top_up"), attr("id", id)];
-    Ok(res)
+        Ok(HandleResponse {
+            messages: vec![],
+            attributes: vec![
+                attr("action", "accept_backup"),
+                attr("id", id),
+                attr("from", old_backup),
+                attr("to", info.sender),
+            ],
+            data: None,
+        })
+    }
 }
 
-fn send_tokens<A: Api>(
-    api: &A,
-    from: &HumanAddr,
-    to: &HumanAddr,
-    balance: &GenericBalance,
-) -> StdResult<Vec<CosmosMsg>> {
-    let native_balance = &balance.native;
-    let mut msgs: Vec<CosmosMsg> = if native_balance.is_empty() {
-        vec![]
-    } else {
-        vec![BankMsg::Send {
-            from_address: from.into(),
-            to_address: to.into(),
-            amount: native_balance.to_vec(),
+pub fn try_transfer_remote<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    info: MessageInfo,
+    from_id: String,
+    contract: HumanAddr,
+    to_id: String,
+    amount: Balance,
+) -> Result<HandleResponse, ContractError> {
+    if amount.is_empty() {
+        return Err(ContractError::EmptyBalance {});
+    }
+    let mut clawback_from = clawbacks_read(&deps.storage).load(from_id.as_bytes())?;
+    let sender = deps.api.canonical_address(&info.sender)?;
+    if sender != clawback_from.holder {
+        if clawback_from.is_expired(&env) || !clawback_from.is_sole_backup(&sender) {
+            return Err(ContractError::Unauthorized {});
         }
-        .into()]
+        if clawback_from.is_disputed() {
+            return Err(ContractError::Disputed {});
+        }
+        // the backup takes the tokens away from the holder
+        take_back(
+            &env,
+            &mut clawback_from,
+            &GenericBalance::from(amount.clone()),
+        )?;
+    } else {
+        // the holder moves the released tokens first
+        clawback_from
+            .released
+            .remove_up_to(&GenericBalance::from(amount.clone()));
+    }
+    execute_transfer_remote(
+        deps,
+        &sender,
+        from_id,
+        clawback_from,
+        contract,
+        to_id,
+        amount,
+    )
+}
+
+fn execute_transfer_remote<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    sender: &CanonicalAddr,
+    from_id: String,
+    mut clawback_from: Clawback,
+    contract: HumanAddr,
+    to_id: String,
+    amount: Balance,
+) -> Result<HandleResponse, ContractError> {
+    assert_not_paused(&deps.storage, |_| false)?;
+    // the tokens may only leave for a contract that keeps them under the clawback
+    let peer = deps.api.canonical_address(&contract)?;
+    if !config_read(&deps.storage).load()?.peers.contains(&peer) {
+        return Err(ContractError::Unauthorized {});
+    }
+    clawback_from
+        .balance
+        .remove_tokens(amount.clone())
+        .map_err(|_| ContractError::NotEnoughBalance {})?;
+    save_clawback(&mut deps.storage, from_id.as_bytes(), &clawback_from)?;
+
+    // the other contract checks these against the receiving clawback
+    let terms = TransferTerms {
+        backup: deps.api.human_address(&clawback_from.backup)?,
+        backup_multisig: human_multisig(&deps.api, clawback_from.backup_multisig)?,
+        clawback_period: clawback_from.clawback_period,
+        cw20_whitelist: clawback_from
+            .cw20_whitelist
+            .iter()
+            .map(|c| deps.api.human_address(c))
+            .collect::<StdResult<_>>()?,
+        native_whitelist: clawback_from.native_whitelist.clone(),
+        sender: Some(deps.api.human_address(sender)?),
+    };
+    let message: CosmosMsg = match amount {
+        Balance::Native(balance) => WasmMsg::Execute {
+            contract_addr: contract.clone(),
+            msg: to_binary(&HandleMsg::TransferIn {
+                id: to_id.clone(),
+                terms,
+            })?,
+            send: balance.0,
+        }
+        .into(),
+        Balance::Cw20(token) => WasmMsg::Execute {
+            contract_addr: deps.api.human_address(&token.address)?,
+            msg: to_binary(&Cw20HandleMsg::Send {
+                contract: contract.clone(),
+                amount: token.amount,
+                msg: Some(to_binary(&ReceiveMsg::TransferIn {
+                    id: to_id.clone(),
+                    terms,
+                })?),
+            })?,
+            send: vec![],
+        }
+        .into(),
     };
+    Ok(HandleResponse {
+        messages: vec![message],
+        attributes: vec![
+            attr("action", "transfer_remote"),
+            attr("from_id", from_id),
+            attr("contract", contract),
+            attr("to_id", to_id),
+        ],
+        data: None,
+    })
+}
 
-    let cw20_balance = &balance.cw20;
-    let cw20_msgs: StdResult<Vec<_>> = cw20_balance
+pub fn try_transfer_in<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    sender: HumanAddr,
+    id: String,
+    terms: TransferTerms,
+    balance: Balance,
+) -> Result<HandleResponse, ContractError> {
+    // anyone could claim any terms, so only trusted contracts may send them
+    let sender = deps.api.canonical_address(&sender)?;
+    if !config_read(&deps.storage).load()?.peers.contains(&sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    if balance.is_empty() {
+        return Err(ContractError::EmptyBalance {});
+    }
+    assert_not_paused(&deps.storage, |_| false)?;
+    assert_allowed(&deps.storage, &balance)?;
+    // this fails is no clawback there
+    let mut clawback = clawbacks_read(&deps.storage).load(id.as_bytes())?;
+
+    // same rules as for a transfer within this contract
+    let backup = deps.api.canonical_address(&terms.backup)?;
+    let backup_multisig = canonical_multisig(&deps.api, terms.backup_multisig)?;
+    let cw20_whitelist = terms
+        .cw20_whitelist
         .iter()
-        .map(|c| {
-            let msg = Cw20HandleMsg::Transfer {
-                recipient: to.into(),
-                amount: c.amount,
-            };
-            let exec = WasmMsg::Execute {
-                contract_addr: api.human_address(&c.address)?,
-                msg: to_binary(&msg)?,
-                send: vec![],
-            };
-            Ok(exec.into())
-        })
-        .collect();
-    msgs.append(&mut cw20_msgs?);
-    Ok(msgs)
-}
+        .map(|h| deps.api.canonical_address(h))
+        .collect::<StdResult<Vec<_>>>()?;
+    if backup != clawback.backup
+        || backup_multisig != clawback.backup_multisig
+        || !clawback.covers_period(&terms.clawback_period)
+        || !clawback.covers_whitelists(&cw20_whitelist, &terms.native_whitelist)
+    {
+        return Err(ContractError::ContractMismatch {});
+    }
+    // ensure the tokens are on the whitelists
+    if !clawback.accepts(&balance) {
+        return Err(ContractError::NotInWhitelist {});
+    }
 
-pub fn query<S: Storage, A: Api, Q: Querier>(
-    deps: &Extern<S, A, Q>,
-    _env: Env,
-    msg: QueryMsg,
-) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::List {} => to_binary(&query_list(deps)?),
-        QueryMsg::Details { id } => to_binary(&query_details(deps, id)?),
+    let sender = match &terms.sender {
+        Some(sender) => Some(deps.api.canonical_address(sender)?),
+        None => None,
+    };
+    let may_deposit = match &sender {
+        Some(sender) => clawback.may_deposit(sender),
+        None => clawback.depositors == Depositors::Anyone {},
+    };
+    if !may_deposit {
+        return Err(ContractError::Unauthorized {});
     }
+    if !clawback.may_refresh_by_transfer(sender.as_ref()) || !clawback.can_refresh(&env) {
+        return Err(ContractError::RefreshNotAllowed {});
+    }
+    clawback.refresh(&env);
+    // the transferred tokens are released over the new period
+    clawback.balance.add_tokens(balance);
+    assert_max_tokens(&deps.storage, &clawback.balance)?;
+    save_clawback(&mut deps.storage, id.as_bytes(), &clawback)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        attributes: vec![attr("action", "transfer_in"), attr("id", id)],
+        data: None,
+    })
 }
 
-fn query_details<S: Storage, A: Api, Q: Querier>(
-    deps: &Extern<S, A, Q>,
+pub fn try_propose<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    info: MessageInfo,
     id: String,
-) -> StdResult<DetailsResponse> {
+    action: BackupAction,
+) -> Result<HandleResponse, ContractError> {
+    if let BackupAction::Transfer { amount, .. } | BackupAction::TransferRemote { amount, .. } =
+        &action
+    {
+        if amount.is_empty() {
+            return Err(ContractError::EmptyBalance {});
+        }
+    }
+    // this fails is no clawback there
     let clawback = clawbacks_read(&deps.storage).load(id.as_bytes())?;
+    let multisig = clawback
+        .backup_multisig
+        .as_ref()
+        .ok_or(ContractError::NoMultisig {})?;
+    let sender = deps.api.canonical_address(&info.sender)?;
+    if clawback.is_expired(&env) || !multisig.signers.contains(&sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    if clawback.is_disputed() {
+        return Err(ContractError::Disputed {});
+    }
 
-    let cw20_whitelist = clawback.human_whitelist(&deps.api)?;
-
-    // transform tokens
-    let native_balance = clawback.balance.native;
+    if action == (BackupAction::Refresh {}) && !clawback.refresh_policy.refresh_by.backup() {
+        return Err(ContractError::RefreshNotAllowed {});
+    }
 
-    let cw20_balance: StdResult<Vec<_>> = clawback
-        .balance
-        .cw20
-        .into_iter()
-        .map(|token| {
-            Ok(Cw20CoinHuman {
-                address: deps.api.human_address(&token.address)?,
-                amount: token.amount,
-            })
-        })
-        .collect();
+    // a new proposal replaces the open one of the same signer and expired ones are dropped,
+    // so there is at most one open proposal per signer on each clawback
+    for proposal_id in clawback_proposal_ids(&deps.storage, id.as_bytes())? {
+        let proposal = proposals_read(&deps.storage).load(&proposal_id.to_be_bytes())?;
+        if proposal.is_expired(&env) || proposal.votes.first() == Some(&sender) {
+            remove_proposal(&mut deps.storage, proposal_id, id.as_bytes());
+        }
+    }
 
-    let details = DetailsResponse {
-        id,
-        backup: deps.api.human_address(&clawback.backup)?,
-        holder: deps.api.human_address(&clawback.holder)?,
-        clawback_period: clawback.clawback_period,
-        end_time: clawback.end_time,
-        native_balance,
-        cw20_balance: cw20_balance?,
-        cw20_whitelist,
+    let proposal_id = next_proposal_id(&mut deps.storage)?;
+    let proposal = Proposal {
+        clawback_id: id,
+        action,
+        votes: vec![sender],
+        expires: clawback.end_time,
     };
-    Ok(details)
+    let attributes = vec![
+        attr("action", "propose"),
+        attr("id", &proposal.clawback_id),
+        attr("proposal_id", proposal_id),
+        attr("voter", info.sender),
+    ];
+    tally(deps, &env, proposal_id, proposal, clawback, attributes)
 }
 
-fn query_list<S: Storage, A: Api, Q: Querier>(deps: &Extern<S, A, Q>) -> StdResult<ListResponse> {
-    Ok(ListResponse {
-        clawbacks: all_clawback_ids(&deps.storage)?,
-    })
+pub fn try_vote<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+) -> Result<HandleResponse, ContractError> {
+    // this fails if the proposal passed or never existed
+    let mut proposal = proposals_read(&deps.storage).load(&proposal_id.to_be_bytes())?;
+    let clawback = clawbacks_read(&deps.storage).load(proposal.clawback_id.as_bytes())?;
+    if proposal.is_expired(&env) || clawback.is_expired(&env) {
+        return Err(ContractError::ProposalExpired {});
+    }
+    let multisig = clawback
+        .backup_multisig
+        .as_ref()
+        .ok_or(ContractError::NoMultisig {})?;
+    let sender = deps.api.canonical_address(&info.sender)?;
+    if !multisig.signers.contains(&sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    if proposal.votes.contains(&sender) {
+        return Err(ContractError::AlreadyVoted {});
+    }
+    proposal.votes.push(sender);
+
+    let attributes = vec![
+        attr("action", "vote"),
+        attr("id", &proposal.clawback_id),
+        attr("proposal_id", proposal_id),
+        attr("voter", info.sender),
+    ];
+    tally(deps, &env, proposal_id, proposal, clawback, attributes)
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::msg::HandleMsg::TopUp;
-    use cosmwasm_std::testing::{
-        mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MOCK_CONTRACT_ADDR,
-    };
-    use cosmwasm_std::{
-        coin, coins, CanonicalAddr, Coin, CosmosMsg, MemoryStorage, StdError, Uint128,
+// this executes the proposed action once enough signers voted for it,
+// otherwise it stores the proposal with the new vote
+fn tally<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    proposal_id: u64,
+    proposal: Proposal,
+    mut clawback: Clawback,
+    mut attributes: Vec<Attribute>,
+) -> Result<HandleResponse, ContractError> {
+    let passed = match clawback.backup_multisig.as_ref() {
+        Some(multisig) => {
+            let votes = proposal
+                .votes
+                .iter()
+                .filter(|v| multisig.signers.contains(v))
+                .count() as u64;
+            votes >= multisig.threshold
+        }
+        None => return Err(ContractError::NoMultisig {}),
     };
-    use cw0::NativeBalance;
+    if !passed {
+        save_proposal(&mut deps.storage, proposal_id, &proposal)?;
+        return Ok(HandleResponse {
+            messages: vec![],
+            attributes,
+            data: None,
+        });
+    }
 
-    use super::*;
+    // no backup action passes until the dispute is resolved
+    if clawback.is_disputed() {
+        return Err(ContractError::Disputed {});
+    }
+    remove_proposal(
+        &mut deps.storage,
+        proposal_id,
+        proposal.clawback_id.as_bytes(),
+    );
+    let id = proposal.clawback_id;
+    if let BackupAction::Transfer { amount, .. } | BackupAction::TransferRemote { amount, .. } =
+        &proposal.action
+    {
+        take_back(env, &mut clawback, &GenericBalance::from(amount.clone()))?;
+    }
+    if let BackupAction::UpdateHolder { .. } = &proposal.action {
+        record_holder_change(&mut clawback)?;
+    }
+    // the multisig acts as the backup
+    let backup = clawback.backup.clone();
+    let mut res = match proposal.action {
+        BackupAction::Refresh {} => execute_refresh(deps, env, id, clawback, false),
+        BackupAction::Burn {} => execute_burn(deps, env, id, clawback),
+        BackupAction::Transfer {
+            to_id,
+            amount,
+            create,
+        } => execute_transfer(deps, env, &backup, id, clawback, to_id, amount, create),
+        BackupAction::TransferRemote {
+            contract,
+            to_id,
+            amount,
+        } => execute_transfer_remote(deps, &backup, id, clawback, contract, to_id, amount),
+        BackupAction::Clawback { recipient, amount } => {
+            execute_clawback(deps, env, id, clawback, recipient, amount)
+        }
+        BackupAction::UpdateHolder { holder } => execute_update_holder(deps, id, clawback, holder),
+        BackupAction::UpdateBackup { backup } => execute_update_backup(deps, id, clawback, backup),
+    }?;
+    attributes.push(attr("executed", "true"));
+    attributes.append(&mut res.attributes);
+    res.attributes = attributes;
+    Ok(res)
+}
 
-    fn basic_native_setup() -> (
-        CreateMsg,
-        u64,
-        u64,
-        Extern<MemoryStorage, MockApi, MockQuerier>,
-        Vec<Coin>,
-    ) {
-        let mut deps = mock_dependencies(&[]);
+pub fn try_dispute<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+    reason: String,
+) -> Result<HandleResponse, ContractError> {
+    // this fails is no clawback there
+    let mut clawback = clawbacks_read(&deps.storage).load(id.as_bytes())?;
+    let sender = deps.api.canonical_address(&info.sender)?;
 
-        // init an empty contract
-        let init_msg = InitMsg {};
-        let mock_clawback_period = 1;
-        let mock_time = 1571920875;
-        let mut init_env = mock_env();
-        init_env.block.time = mock_time;
+    if clawback.is_expired(&env) || sender != clawback.holder {
+        return Err(ContractError::Unauthorized {});
+    }
+    if clawback.arbiter.is_none() {
+        return Err(ContractError::NoArbiter {});
+    }
+    if clawback.dispute.is_some() {
+        return Err(ContractError::AlreadyDisputed {});
+    }
+    clawback.dispute = Some(Dispute {
+        reason: reason.clone(),
+        resolved: false,
+    });
+    save_clawback(&mut deps.storage, id.as_bytes(), &clawback)?;
 
-        let info = mock_info(&HumanAddr::from("anyone"), &[]);
-        let res = init(&mut deps, init_env.clone(), info, init_msg).unwrap();
-        assert_eq!(0, res.messages.len());
+    Ok(HandleResponse {
+        messages: vec![],
+        attributes: vec![
+            attr("action", "dispute"),
+            attr("id", id),
+            attr("reason", reason),
+        ],
+        data: None,
+    })
+}
 
-        // create a clawback
-        let create = CreateMsg {
-            id: "foobar".to_string(),
-            backup: HumanAddr::from("backup"),
-            holder: HumanAddr::from("holder"),
-            clawback_period: mock_clawback_period,
-            cw20_whitelist: None,
-        };
-        let sender = HumanAddr::from("source");
-        let balance = coins(100, "tokens");
-        let info = mock_info(&sender, &balance);
-        let msg = HandleMsg::Create(create.clone());
-        let res = handle(&mut deps, init_env, info, msg).unwrap();
-        assert_eq!(0, res.messages.len());
-        assert_eq!(attr("action", "create"), res.attributes[0]);
+pub fn try_resolve<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+    outcome: DisputeOutcome,
+) -> Result<HandleResponse, ContractError> {
+    // this fails is no clawback there
+    let mut clawback = clawbacks_read(&deps.storage).load(id.as_bytes())?;
+    let sender = deps.api.canonical_address(&info.sender)?;
 
-        // ensure the details is what we expect
-        let details = query_details(&deps, "foobar".to_string()).unwrap();
-        assert_eq!(
-            details,
-            DetailsResponse {
-                id: "foobar".to_string(),
-                backup: HumanAddr::from("backup"),
-                holder: HumanAddr::from("holder"),
-                clawback_period: mock_clawback_period,
-                end_time: mock_time + mock_clawback_period,
-                native_balance: balance.clone(),
-                cw20_balance: vec![],
-                cw20_whitelist: vec![],
-            }
-        );
-        (create, mock_time, mock_clawback_period, deps, balance)
+    if clawback.arbiter.as_ref() != Some(&sender) {
+        return Err(ContractError::Unauthorized {});
     }
+    if !clawback.is_disputed() {
+        return Err(ContractError::NotDisputed {});
+    }
+    let (messages, decision) = match outcome {
+        DisputeOutcome::Holder {} => {
+            assert_not_paused(&deps.storage, |policy| policy.holder_withdraw)?;
+            // the holder gets everything, we delete the clawback
+            remove_clawback(&mut deps.storage, id.as_bytes())?;
+            let holder = deps.api.human_address(&clawback.holder)?;
+            let messages = send_tokens(
+                &deps.api,
+                &env.contract.address,
+                &holder,
+                &clawback.balance,
+                None,
+            )?;
+            (messages, "holder")
+        }
+        DisputeOutcome::Backup {} => {
+            if let Some(dispute) = clawback.dispute.as_mut() {
+                dispute.resolved = true;
+            }
+            save_clawback(&mut deps.storage, id.as_bytes(), &clawback)?;
+            (vec![], "backup")
+        }
+    };
 
-    #[test]
-    fn happy_path_native() {
-        let (create, mock_time, mock_clawback_period, mut deps, balance) = basic_native_setup();
-
-        // withdraw it
-        let id = create.id.clone();
-        let info = mock_info(&create.holder, &[]);
-        let mut new_env = mock_env();
-        new_env.block.time = mock_time + mock_clawback_period + 1;
-        let res = handle(&mut deps, new_env.clone(), info, HandleMsg::Withdraw { id }).unwrap();
-        assert_eq!(1, res.messages.len());
-        assert_eq!(attr("action", "withdraw"), res.attributes[0]);
-        assert_eq!(
-            res.messages[0],
-            CosmosMsg::Bank(BankMsg::Send {
-                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
-                to_address: create.holder.clone(),
-                amount: balance,
-            })
-        );
+    Ok(HandleResponse {
+        messages,
+        attributes: vec![
+            attr("action", "resolve"),
+            attr("id", id),
+            attr("outcome", decision),
+        ],
+        data: None,
+    })
+}
 
-        // second attempt fails (not found)
-        let id = create.id.clone();
-        let info = mock_info(&create.holder, &[]);
-        let res = handle(&mut deps, new_env, info, HandleMsg::Withdraw { id });
-        match res.unwrap_err() {
-            ContractError::Std(StdError::NotFound { .. }) => {}
-            e => panic!("Expected NotFound, got {}", e),
-        }
+pub fn try_pause<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    info: MessageInfo,
+    emergency_policy: Option<EmergencyPolicy>,
+) -> Result<HandleResponse, ContractError> {
+    let mut cfg = config_read(&deps.storage).load()?;
+    let sender = deps.api.canonical_address(&info.sender)?;
+    if cfg.admin != Some(sender) {
+        return Err(ContractError::Unauthorized {});
     }
+    cfg.paused = true;
+    if let Some(policy) = emergency_policy {
+        cfg.emergency_policy = policy;
+    }
+    config(&mut deps.storage).save(&cfg)?;
 
-    #[test]
-    fn happy_path_cw20() {
-        let mut deps = mock_dependencies(&[]);
-        let mock_time = 1571920875;
-        let mut init_env = mock_env();
+    Ok(HandleResponse {
+        messages: vec![],
+        attributes: vec![attr("action", "pause")],
+        data: None,
+    })
+}
+
+pub fn try_unpause<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    info: MessageInfo,
+) -> Result<HandleResponse, ContractError> {
+    let mut cfg = config_read(&deps.storage).load()?;
+    let sender = deps.api.canonical_address(&info.sender)?;
+    if cfg.admin != Some(sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    cfg.paused = false;
+    config(&mut deps.storage).save(&cfg)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        attributes: vec![attr("action", "unpause")],
+        data: None,
+    })
+}
+
+pub fn try_update_allowlist<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    info: MessageInfo,
+    allowlist: Option<TokenAllowlist>,
+    max_tokens: Option<u32>,
+) -> Result<HandleResponse, ContractError> {
+    let mut cfg = config_read(&deps.storage).load()?;
+    let sender = deps.api.canonical_address(&info.sender)?;
+    if cfg.admin != Some(sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    cfg.allowlist = canonical_allowlist(&deps.api, allowlist)?;
+    cfg.max_tokens = max_tokens;
+    config(&mut deps.storage).save(&cfg)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        attributes: vec![attr("action", "update_allowlist")],
+        data: None,
+    })
+}
+
+pub fn try_update_peers<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    info: MessageInfo,
+    peers: Vec<HumanAddr>,
+) -> Result<HandleResponse, ContractError> {
+    let mut cfg = config_read(&deps.storage).load()?;
+    let sender = deps.api.canonical_address(&info.sender)?;
+    if cfg.admin != Some(sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    cfg.peers = canonical_peers(&deps.api, &peers)?;
+    config(&mut deps.storage).save(&cfg)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        attributes: vec![attr("action", "update_peers")],
+        data: None,
+    })
+}
+
+fn canonical_peers<A: Api>(api: &A, peers: &[HumanAddr]) -> StdResult<Vec<CanonicalAddr>> {
+    peers.iter().map(|h| api.canonical_address(h)).collect()
+}
+
+fn canonical_allowlist<A: Api>(
+    api: &A,
+    allowlist: Option<TokenAllowlist>,
+) -> StdResult<Option<Allowlist>> {
+    let allowlist = match allowlist {
+        Some(allowlist) => allowlist,
+        None => return Ok(None),
+    };
+    Ok(Some(Allowlist {
+        native: allowlist.native,
+        cw20: allowlist
+            .cw20
+            .iter()
+            .map(|h| api.canonical_address(h))
+            .collect::<StdResult<_>>()?,
+    }))
+}
+
+/// This fails if any of the incoming tokens is not on the allowlist of the contract
+fn assert_allowed<S: Storage>(storage: &S, balance: &Balance) -> Result<(), ContractError> {
+    if !config_read(storage).load()?.allows(balance) {
+        return Err(ContractError::TokenNotAllowed {});
+    }
+    Ok(())
+}
+
+/// This fails if the balance of a clawback holds more different tokens than allowed
+fn assert_max_tokens<S: Storage>(
+    storage: &S,
+    balance: &GenericBalance,
+) -> Result<(), ContractError> {
+    if let Some(max) = config_read(storage).load()?.max_tokens {
+        if balance.token_count() > max as usize {
+            return Err(ContractError::TooManyTokens { max });
+        }
+    }
+    Ok(())
+}
+
+/// This fails while the contract is paused, unless the emergency policy allows the action
+fn assert_not_paused<S: Storage>(
+    storage: &S,
+    allowed: fn(&EmergencyPolicy) -> bool,
+) -> Result<(), ContractError> {
+    let cfg = config_read(storage).load()?;
+    if cfg.paused && !allowed(&cfg.emergency_policy) {
+        return Err(ContractError::Paused {});
+    }
+    Ok(())
+}
+
+pub fn try_receive<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+) -> Result<HandleResponse, ContractError> {
+    let msg: ReceiveMsg = match wrapper.msg {
+        Some(bin) => Ok(from_binary(&bin)?),
+        None => Err(ContractError::NoData {}),
+    }?;
+    let balance = Balance::Cw20(Cw20Coin {
+        address: deps.api.canonical_address(&info.sender)?,
+        amount: wrapper.amount,
+    });
+    match msg {
+        ReceiveMsg::Create(msg) => try_create(deps, env, wrapper.sender, msg, balance),
+        ReceiveMsg::TopUp { id } => try_top_up(deps, env, wrapper.sender, id, balance),
+        ReceiveMsg::TransferIn { id, terms } => {
+            try_transfer_in(deps, env, wrapper.sender, id, terms, balance)
+        }
+    }
+}
+
+pub fn try_create<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    sender: HumanAddr,
+    msg: CreateMsg,
+    balance: Balance,
+) -> Result<HandleResponse, ContractError> {
+    if let Some(id) = &msg.id {
+        if !is_valid_name(id) {
+            return Err(ContractError::InvalidId {});
+        }
+    }
+    let unlisted_denoms = msg.unlisted_denoms.clone().unwrap_or_default();
+    let (balance, refund) = split_unlisted(
+        &env,
+        &sender,
+        &msg.native_whitelist,
+        &unlisted_denoms,
+        balance,
+    )?;
+    if balance.is_empty() {
+        return Err(ContractError::EmptyBalance {});
+    }
+    assert_not_paused(&deps.storage, |_| false)?;
+
+    let mut cw20_whitelist = msg.canonical_whitelist(&deps.api)?;
+    // no whitelisting of tokens that could never be topped up
+    let cfg = config_read(&deps.storage).load()?;
+    let native_whitelisted = match &msg.native_whitelist {
+        Some(denoms) => denoms.iter().all(|d| cfg.allows_native(d)),
+        None => true,
+    };
+    if !cfg.allows(&balance)
+        || !native_whitelisted
+        || !cw20_whitelist.iter().all(|c| cfg.allows_cw20(c))
+    {
+        return Err(ContractError::TokenNotAllowed {});
+    }
+    let refresh_policy = msg.refresh_policy.unwrap_or_default();
+
+    let clawback_balance = match balance {
+        Balance::Native(balance) => GenericBalance {
+            native: balance.0,
+            cw20: vec![],
+        },
+        Balance::Cw20(token) => {
+            // make sure the token sent is on the whitelist by default
+            if !cw20_whitelist.iter().any(|t| t == &token.address) {
+                cw20_whitelist.push(token.address.clone())
+            }
+            GenericBalance {
+                native: vec![],
+                cw20: vec![token],
+            }
+        }
+    };
+
+    assert_max_tokens(&deps.storage, &clawback_balance)?;
+
+    let clawback = Clawback {
+        backup: deps.api.canonical_address(&msg.backup)?,
+        holder: deps.api.canonical_address(&msg.holder)?,
+        clawback_period: msg.clawback_period,
+        end_time: msg.clawback_period.after(&env.block),
+        balance: clawback_balance,
+        cw20_whitelist,
+        backup_multisig: canonical_multisig(&deps.api, msg.backup_multisig)?,
+        pending_backup: None,
+        max_end_time: max_end_time(&env, &msg.clawback_period, &refresh_policy)?,
+        refresh_policy,
+        refreshes: 0,
+        release: release_schedule(&msg.clawback_period, msg.release)?,
+        withdrawn: GenericBalance::default(),
+        taken_back: GenericBalance::default(),
+        released: GenericBalance::default(),
+        max_clawback: canonical_limit(&deps.api, msg.max_clawback)?,
+        clawed_back: GenericBalance::default(),
+        arbiter: match msg.arbiter {
+            Some(arbiter) => Some(deps.api.canonical_address(&arbiter)?),
+            None => None,
+        },
+        dispute: None,
+        depositors: canonical_depositors(&deps.api, msg.depositors)?,
+        native_whitelist: msg.native_whitelist,
+        unlisted_denoms,
+    };
+
+    let id = match msg.id {
+        // try to store it, fail if the id was already in use
+        Some(id) => {
+            if clawbacks_read(&deps.storage)
+                .may_load(id.as_bytes())?
+                .is_some()
+            {
+                return Err(ContractError::AlreadyInUse {});
+            }
+            id
+        }
+        None => next_clawback_id(&mut deps.storage)?,
+    };
+    save_clawback(&mut deps.storage, id.as_bytes(), &clawback)?;
+
+    Ok(HandleResponse {
+        messages: refund,
+        attributes: vec![attr("action", "create"), attr("id", &id)],
+        data: Some(to_binary(&CreateResponse { id })?),
+    })
+}
+
+/// This splits off the sent native tokens that are not on the native whitelist,
+/// and returns a message refunding them to the sender (or fails, if they are rejected)
+fn split_unlisted(
+    env: &Env,
+    sender: &HumanAddr,
+    native_whitelist: &Option<Vec<String>>,
+    unlisted_denoms: &UnlistedDenoms,
+    balance: Balance,
+) -> Result<(Balance, Vec<CosmosMsg>), ContractError> {
+    let (whitelist, coins) = match (native_whitelist, balance) {
+        (Some(whitelist), Balance::Native(coins)) => (whitelist, coins.0),
+        (_, balance) => return Ok((balance, vec![])),
+    };
+    let (listed, unlisted): (Vec<_>, Vec<_>) = coins
+        .into_iter()
+        .partition(|c| whitelist.contains(&c.denom));
+    let refund = match (unlisted.is_empty(), unlisted_denoms) {
+        (true, _) => vec![],
+        (false, UnlistedDenoms::Reject {}) => return Err(ContractError::NotInWhitelist {}),
+        (false, UnlistedDenoms::Refund {}) => vec![BankMsg::Send {
+            from_address: env.contract.address.clone(),
+            to_address: sender.clone(),
+            amount: unlisted,
+        }
+        .into()],
+    };
+    Ok((Balance::from(listed), refund))
+}
+
+/// This returns the latest end time / height a refresh may set
+/// for a clawback created in this block
+fn max_end_time(
+    env: &Env,
+    period: &Duration,
+    policy: &RefreshPolicy,
+) -> Result<Option<Expiration>, ContractError> {
+    match (period, policy.max_lock) {
+        (_, None) => Ok(None),
+        (Duration::Time(_), Some(max_lock @ Duration::Time(_)))
+        | (Duration::Height(_), Some(max_lock @ Duration::Height(_))) => {
+            Ok(Some(max_lock.after(&env.block)))
+        }
+        _ => Err(ContractError::InvalidRefreshPolicy {}),
+    }
+}
+
+/// This checks that a stepped release has between 1 and as many steps
+/// as the clawback period has seconds / blocks
+fn release_schedule(
+    period: &Duration,
+    release: Option<ReleaseSchedule>,
+) -> Result<ReleaseSchedule, ContractError> {
+    let release = release.unwrap_or_default();
+    if let ReleaseSchedule::Step { steps } = release {
+        let period = match period {
+            Duration::Time(period) | Duration::Height(period) => *period,
+        };
+        if steps == 0 || steps > period {
+            return Err(ContractError::InvalidRelease {});
+        }
+    }
+    Ok(release)
+}
+
+fn canonical_multisig<A: Api>(
+    api: &A,
+    multisig: Option<Multisig>,
+) -> Result<Option<BackupMultisig>, ContractError> {
+    let multisig = match multisig {
+        Some(multisig) => multisig,
+        None => return Ok(None),
+    };
+    let mut signers = multisig
+        .signers
+        .iter()
+        .map(|h| api.canonical_address(h))
+        .collect::<StdResult<Vec<_>>>()?;
+    // sorted, so that equal sets compare equal
+    signers.sort_by(|a, b| a.as_slice().cmp(b.as_slice()));
+    signers.dedup();
+    if multisig.threshold == 0 || multisig.threshold > signers.len() as u64 {
+        return Err(ContractError::InvalidThreshold {});
+    }
+    Ok(Some(BackupMultisig {
+        signers,
+        threshold: multisig.threshold,
+    }))
+}
+
+fn canonical_limit<A: Api>(
+    api: &A,
+    limit: Option<ClawbackLimit>,
+) -> StdResult<Option<GenericBalance>> {
+    let limit = match limit {
+        Some(limit) => limit,
+        None => return Ok(None),
+    };
+    let mut max = GenericBalance::default();
+    max.add_tokens(Balance::from(limit.native));
+    for token in limit.cw20.into_iter() {
+        max.add_tokens(Balance::Cw20(Cw20Coin {
+            address: api.canonical_address(&token.address)?,
+            amount: token.amount,
+        }));
+    }
+    Ok(Some(max))
+}
+
+fn human_limit<A: Api>(api: &A, limit: Option<GenericBalance>) -> StdResult<Option<ClawbackLimit>> {
+    let limit = match limit {
+        Some(limit) => limit,
+        None => return Ok(None),
+    };
+    Ok(Some(ClawbackLimit {
+        native: limit.native,
+        cw20: human_cw20(api, limit.cw20)?,
+    }))
+}
+
+fn canonical_depositors<A: Api>(
+    api: &A,
+    depositors: Option<DepositPolicy>,
+) -> StdResult<Depositors> {
+    Ok(match depositors.unwrap_or_default() {
+        DepositPolicy::Anyone {} => Depositors::Anyone {},
+        DepositPolicy::HolderAndBackup {} => Depositors::HolderAndBackup {},
+        DepositPolicy::Only { depositors } => Depositors::Only {
+            depositors: depositors
+                .iter()
+                .map(|h| api.canonical_address(h))
+                .collect::<StdResult<_>>()?,
+        },
+    })
+}
+
+fn human_depositors<A: Api>(api: &A, depositors: Depositors) -> StdResult<DepositPolicy> {
+    Ok(match depositors {
+        Depositors::Anyone {} => DepositPolicy::Anyone {},
+        Depositors::HolderAndBackup {} => DepositPolicy::HolderAndBackup {},
+        Depositors::Only { depositors } => DepositPolicy::Only {
+            depositors: depositors
+                .iter()
+                .map(|c| api.human_address(c))
+                .collect::<StdResult<_>>()?,
+        },
+    })
+}
+
+fn human_multisig<A: Api>(
+    api: &A,
+    multisig: Option<BackupMultisig>,
+) -> StdResult<Option<Multisig>> {
+    let multisig = match multisig {
+        Some(multisig) => multisig,
+        None => return Ok(None),
+    };
+    Ok(Some(Multisig {
+        signers: multisig
+            .signers
+            .iter()
+            .map(|c| api.human_address(c))
+            .collect::<StdResult<_>>()?,
+        threshold: multisig.threshold,
+    }))
+}
+
+pub fn try_top_up<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    sender: HumanAddr,
+    id: String,
+    balance: Balance,
+) -> Result<HandleResponse, ContractError> {
+    if balance.is_empty() {
+        return Err(ContractError::EmptyBalance {});
+    }
+    assert_not_paused(&deps.storage, |_| false)?;
+    // this fails is no clawback there
+    let mut clawback = clawbacks_read(&deps.storage).load(id.as_bytes())?;
+
+    if !clawback.may_deposit(&deps.api.canonical_address(&sender)?) {
+        return Err(ContractError::Unauthorized {});
+    }
+    let (balance, refund) = split_unlisted(
+        &env,
+        &sender,
+        &clawback.native_whitelist,
+        &clawback.unlisted_denoms,
+        balance,
+    )?;
+    if balance.is_empty() {
+        return Err(ContractError::EmptyBalance {});
+    }
+    assert_allowed(&deps.storage, &balance)?;
+    if let Balance::Cw20(token) = &balance {
+        // ensure the token is on the whitelist
+        if !clawback.cw20_whitelist.iter().any(|t| t == &token.address) {
+            return Err(ContractError::NotInWhitelist {});
+        }
+    };
+
+    clawback.balance.add_tokens(balance);
+    assert_max_tokens(&deps.storage, &clawback.balance)?;
+
+    // and save
+    save_clawback(&mut deps.storage, id.as_bytes(), &clawback)?;
+
+    Ok(HandleResponse {
+        messages: refund,
+        attributes: vec![
+            attr("action", "top_up"),
+            attr("id", id),
+            attr("sender", sender),
+        ],
+        data: None,
+    })
+}
+
+/// Sends the balance to the recipient: if msg is set, cw20 tokens are sent
+/// with `Cw20HandleMsg::Send` to trigger the receiving contract
+fn send_tokens<A: Api>(
+    api: &A,
+    from: &HumanAddr,
+    to: &HumanAddr,
+    balance: &GenericBalance,
+    msg: Option<Binary>,
+) -> StdResult<Vec<CosmosMsg>> {
+    let native_balance = &balance.native;
+    let mut msgs: Vec<CosmosMsg> = if native_balance.is_empty() {
+        vec![]
+    } else {
+        vec![BankMsg::Send {
+            from_address: from.into(),
+            to_address: to.into(),
+            amount: native_balance.to_vec(),
+        }
+        .into()]
+    };
+
+    let cw20_balance = &balance.cw20;
+    let cw20_msgs: StdResult<Vec<_>> = cw20_balance
+        .iter()
+        .map(|c| {
+            let msg = match &msg {
+                Some(msg) => Cw20HandleMsg::Send {
+                    contract: to.into(),
+                    amount: c.amount,
+                    msg: Some(msg.clone()),
+                },
+                None => Cw20HandleMsg::Transfer {
+                    recipient: to.into(),
+                    amount: c.amount,
+                },
+            };
+            let exec = WasmMsg::Execute {
+                contract_addr: api.human_address(&c.address)?,
+                msg: to_binary(&msg)?,
+                send: vec![],
+            };
+            Ok(exec.into())
+        })
+        .collect();
+    msgs.append(&mut cw20_msgs?);
+    Ok(msgs)
+}
+
+fn burn_tokens<A: Api>(
+    api: &A,
+    from: &HumanAddr,
+    native_sink: Option<&HumanAddr>,
+    balance: &GenericBalance,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let native_balance: Vec<_> = balance
+        .native
+        .iter()
+        .filter(|c| !c.amount.is_zero())
+        .cloned()
+        .collect();
+    let mut msgs: Vec<CosmosMsg> = if native_balance.is_empty() {
+        vec![]
+    } else {
+        // there is no bank burn message, so native tokens go to the sink
+        let sink = native_sink.ok_or(ContractError::NoNativeSink {})?;
+        vec![BankMsg::Send {
+            from_address: from.into(),
+            to_address: sink.into(),
+            amount: native_balance,
+        }
+        .into()]
+    };
+
+    let cw20_msgs: StdResult<Vec<_>> = balance
+        .cw20
+        .iter()
+        .filter(|c| !c.amount.is_zero())
+        .map(|c| {
+            let msg = Cw20HandleMsg::Burn { amount: c.amount };
+            let exec = WasmMsg::Execute {
+                contract_addr: api.human_address(&c.address)?,
+                msg: to_binary(&msg)?,
+                send: vec![],
+            };
+            Ok(exec.into())
+        })
+        .collect();
+    msgs.append(&mut cw20_msgs?);
+    Ok(msgs)
+}
+
+// how many clawbacks a migration converts at most
+const DEFAULT_MIGRATE_LIMIT: u32 = 100;
+const MAX_MIGRATE_LIMIT: u32 = 500;
+
+pub fn migrate<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    _env: Env,
+    _info: MessageInfo,
+    msg: MigrateMsg,
+) -> Result<MigrateResponse, ContractError> {
+    let previous = get_contract_version(&deps.storage)?;
+    if previous.contract != CONTRACT_NAME {
+        return Err(ContractError::CannotMigrate {
+            previous_contract: previous.contract,
+        });
+    }
+    let previous_version =
+        parse_version(&previous.version).ok_or_else(|| ContractError::CannotMigrateVersion {
+            previous_version: previous.version.clone(),
+        })?;
+    let current_version = parse_version(CONTRACT_VERSION)
+        .ok_or_else(|| StdError::generic_err("Invalid contract version"))?;
+    // no downgrades
+    if previous_version > current_version {
+        return Err(ContractError::CannotMigrateVersion {
+            previous_version: previous.version,
+        });
+    }
+
+    // per-version storage transforms
+    if previous_version < (0, 4, 0) {
+        // there was no config nor total locked balance
+        if config_read(&deps.storage).may_load()?.is_none() {
+            config(&mut deps.storage).save(&Config::default())?;
+        }
+        total_locked(&mut deps.storage).save(&GenericBalance::default())?;
+        migration_cursor(&mut deps.storage).save(&vec![])?;
+    }
+    // periods became cw0 durations, the clawbacks are converted in batches
+    // (so a large store may take several migrations)
+    let mut pending = false;
+    if let Some(cursor) = migration_cursor_read(&deps.storage).may_load()? {
+        let start_after = if cursor.is_empty() {
+            None
+        } else {
+            Some(cursor)
+        };
+        let limit = msg
+            .limit
+            .unwrap_or(DEFAULT_MIGRATE_LIMIT)
+            .clamp(1, MAX_MIGRATE_LIMIT) as usize;
+        match migrate_clawbacks_v0_3(&mut deps.storage, start_after, limit)? {
+            Some(last) => {
+                migration_cursor(&mut deps.storage).save(&last)?;
+                pending = true;
+            }
+            None => migration_cursor(&mut deps.storage).remove(),
+        }
+    }
+
+    if let Some(sink) = msg.native_sink {
+        let native_sink = Some(deps.api.canonical_address(&sink)?);
+        config(&mut deps.storage).update(|mut cfg| -> StdResult<_> {
+            cfg.native_sink = native_sink;
+            Ok(cfg)
+        })?;
+    }
+    if let Some(admin) = msg.admin {
+        let admin = Some(deps.api.canonical_address(&admin)?);
+        config(&mut deps.storage).update(|mut cfg| -> StdResult<_> {
+            cfg.admin = admin;
+            Ok(cfg)
+        })?;
+    }
+
+    set_contract_version(&mut deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    Ok(MigrateResponse {
+        messages: vec![],
+        attributes: vec![
+            attr("action", "migrate"),
+            attr("from_version", previous.version),
+            attr("to_version", CONTRACT_VERSION),
+            attr("pending", pending),
+        ],
+        data: None,
+    })
+}
+
+/// parses "major.minor.patch" (ignoring any pre-release or build suffix)
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let mut parts = version
+        .split(&['-', '+'][..])
+        .next()?
+        .split('.')
+        .map(|p| p.parse::<u64>().ok());
+    let version = (parts.next()??, parts.next()??, parts.next()??);
+    match parts.next() {
+        None => Some(version),
+        Some(_) => None,
+    }
+}
+
+pub fn query<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    env: Env,
+    msg: QueryMsg,
+) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::List { start_after, limit } => to_binary(&query_list(deps, start_after, limit)?),
+        QueryMsg::ListDetails { start_after, limit } => {
+            to_binary(&query_list_details(deps, env, start_after, limit)?)
+        }
+        QueryMsg::ByHolder {
+            holder,
+            start_after,
+            limit,
+        } => to_binary(&query_by_address(
+            deps,
+            PREFIX_HOLDER_INDEX,
+            holder,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::ByBackup {
+            backup,
+            start_after,
+            limit,
+        } => to_binary(&query_by_address(
+            deps,
+            PREFIX_BACKUP_INDEX,
+            backup,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::ExpiringBetween {
+            start,
+            end,
+            start_after,
+            limit,
+        } => to_binary(&query_expiring_between(
+            deps,
+            start,
+            end,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::Expired { start_after, limit } => {
+            to_binary(&query_expired(deps, env, start_after, limit)?)
+        }
+        QueryMsg::Details { id } => to_binary(&query_details(deps, env, id)?),
+        QueryMsg::TotalLocked {} => to_binary(&query_total_locked(deps)?),
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::Proposal { proposal_id } => to_binary(&query_proposal(deps, proposal_id)?),
+        QueryMsg::ListProposals { start_after, limit } => {
+            to_binary(&query_list_proposals(deps, start_after, limit)?)
+        }
+    }
+}
+
+fn query_details<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    env: Env,
+    id: String,
+) -> StdResult<DetailsResponse> {
+    let clawback = clawbacks_read(&deps.storage).load(id.as_bytes())?;
+    details_response(&deps.api, &env, id, clawback)
+}
+
+fn human_cw20<A: Api>(api: &A, tokens: Vec<Cw20Coin>) -> StdResult<Vec<Cw20CoinHuman>> {
+    tokens
+        .into_iter()
+        .map(|token| {
+            Ok(Cw20CoinHuman {
+                address: api.human_address(&token.address)?,
+                amount: token.amount,
+            })
+        })
+        .collect()
+}
+
+fn details_response<A: Api>(
+    api: &A,
+    env: &Env,
+    id: String,
+    clawback: Clawback,
+) -> StdResult<DetailsResponse> {
+    let cw20_whitelist = clawback.human_whitelist(api)?;
+
+    // split the balance into the released part and the rest
+    let vested = clawback.withdrawable(env);
+    let mut unvested = clawback.balance.clone();
+    unvested
+        .remove_balance(&vested)
+        .map_err(|_| StdError::generic_err("released more than the balance"))?;
+
+    let details = DetailsResponse {
+        id,
+        backup: api.human_address(&clawback.backup)?,
+        holder: api.human_address(&clawback.holder)?,
+        clawback_period: clawback.clawback_period,
+        end_time: clawback.end_time,
+        native_balance: clawback.balance.native,
+        cw20_balance: human_cw20(api, clawback.balance.cw20)?,
+        cw20_whitelist,
+        backup_multisig: human_multisig(api, clawback.backup_multisig)?,
+        pending_backup: match clawback.pending_backup {
+            Some(backup) => Some(api.human_address(&backup)?),
+            None => None,
+        },
+        refresh_policy: clawback.refresh_policy,
+        max_end_time: clawback.max_end_time,
+        refreshes: clawback.refreshes,
+        release: clawback.release,
+        vested_native: vested.native,
+        vested_cw20: human_cw20(api, vested.cw20)?,
+        unvested_native: unvested.native,
+        unvested_cw20: human_cw20(api, unvested.cw20)?,
+        max_clawback: human_limit(api, clawback.max_clawback)?,
+        clawed_back_native: clawback.clawed_back.native,
+        clawed_back_cw20: human_cw20(api, clawback.clawed_back.cw20)?,
+        arbiter: match clawback.arbiter {
+            Some(arbiter) => Some(api.human_address(&arbiter)?),
+            None => None,
+        },
+        dispute: clawback.dispute,
+        depositors: human_depositors(api, clawback.depositors)?,
+        native_whitelist: clawback.native_whitelist,
+        unlisted_denoms: clawback.unlisted_denoms,
+    };
+    Ok(details)
+}
+
+fn query_config<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<ConfigResponse> {
+    let cfg = config_read(&deps.storage).load()?;
+    Ok(ConfigResponse {
+        admin: match cfg.admin {
+            Some(admin) => Some(deps.api.human_address(&admin)?),
+            None => None,
+        },
+        native_sink: match cfg.native_sink {
+            Some(sink) => Some(deps.api.human_address(&sink)?),
+            None => None,
+        },
+        paused: cfg.paused,
+        emergency_policy: cfg.emergency_policy,
+        allowlist: match cfg.allowlist {
+            Some(allowlist) => Some(TokenAllowlist {
+                native: allowlist.native,
+                cw20: allowlist
+                    .cw20
+                    .iter()
+                    .map(|c| deps.api.human_address(c))
+                    .collect::<StdResult<_>>()?,
+            }),
+            None => None,
+        },
+        max_tokens: cfg.max_tokens,
+        peers: cfg
+            .peers
+            .iter()
+            .map(|c| deps.api.human_address(c))
+            .collect::<StdResult<_>>()?,
+    })
+}
+
+fn query_total_locked<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<TotalLockedResponse> {
+    let total = total_locked_read(&deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+
+    let native = total
+        .native
+        .into_iter()
+        .filter(|c| !c.amount.is_zero())
+        .collect();
+    let cw20: StdResult<Vec<_>> = total
+        .cw20
+        .into_iter()
+        .filter(|c| !c.amount.is_zero())
+        .map(|token| {
+            Ok(Cw20CoinHuman {
+                address: deps.api.human_address(&token.address)?,
+                amount: token.amount,
+            })
+        })
+        .collect();
+
+    Ok(TotalLockedResponse {
+        native,
+        cw20: cw20?,
+    })
+}
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+fn query_list<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ListResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    Ok(ListResponse {
+        clawbacks: clawback_ids(&deps.storage, start_after, limit)?,
+    })
+}
+
+fn query_by_address<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    index: &[u8],
+    address: HumanAddr,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ListResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let address = deps.api.canonical_address(&address)?;
+    Ok(ListResponse {
+        clawbacks: address_index_ids(&deps.storage, index, &address, start_after, limit)?,
+    })
+}
+
+fn query_list_details<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ListDetailsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let clawbacks: StdResult<Vec<_>> = range_clawbacks(&deps.storage, start_after, limit)?
+        .into_iter()
+        .map(|(id, clawback)| details_response(&deps.api, &env, id, clawback))
+        .collect();
+    Ok(ListDetailsResponse {
+        clawbacks: clawbacks?,
+    })
+}
+
+fn query_proposal<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    proposal_id: u64,
+) -> StdResult<ProposalResponse> {
+    let proposal = proposals_read(&deps.storage).load(&proposal_id.to_be_bytes())?;
+    proposal_response(&deps.api, proposal_id, proposal)
+}
+
+fn proposal_response<A: Api>(api: &A, id: u64, proposal: Proposal) -> StdResult<ProposalResponse> {
+    Ok(ProposalResponse {
+        id,
+        clawback_id: proposal.clawback_id,
+        action: proposal.action,
+        votes: proposal
+            .votes
+            .iter()
+            .map(|c| api.human_address(c))
+            .collect::<StdResult<_>>()?,
+        expires: proposal.expires,
+    })
+}
+
+fn query_list_proposals<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ListProposalsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let proposals: StdResult<Vec<_>> = range_proposals(&deps.storage, start_after, limit)?
+        .into_iter()
+        .map(|(id, proposal)| proposal_response(&deps.api, id, proposal))
+        .collect();
+    Ok(ListProposalsResponse {
+        proposals: proposals?,
+    })
+}
+
+// this returns the first expiry index key to list, given the start of the window
+fn expiry_range_start(start: Vec<u8>, start_after: &Option<ExpiringClawback>) -> Vec<u8> {
+    match start_after
+        .as_ref()
+        .and_then(|after| expiry_key(&after.end_time, after.id.as_bytes()))
+    {
+        Some(mut after) => {
+            // just past the last listed key
+            after.push(0);
+            after.max(start)
+        }
+        None => start,
+    }
+}
+
+fn to_expiring_response(items: Vec<(String, Expiration)>) -> ExpiringResponse {
+    ExpiringResponse {
+        clawbacks: items
+            .into_iter()
+            .map(|(id, end_time)| ExpiringClawback { id, end_time })
+            .collect(),
+    }
+}
+
+fn query_expiring_between<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    start: Expiration,
+    end: Expiration,
+    start_after: Option<ExpiringClawback>,
+    limit: Option<u32>,
+) -> StdResult<ExpiringResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let (start, mut end) = match (expiry_prefix(&start), expiry_prefix(&end)) {
+        (Some(start), Some(end)) if start[0] == end[0] => (start, end),
+        _ => {
+            return Err(StdError::generic_err(
+                "start and end must both be a time or both be a height",
+            ))
+        }
+    };
+    // include all ids at the end time / height (0xff is never part of a utf8 id)
+    end.push(0xff);
+    let start = expiry_range_start(start, &start_after);
+    let items = expiry_index_range(&deps.storage, &start, &end, limit)?;
+    Ok(to_expiring_response(items))
+}
+
+fn query_expired<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    env: Env,
+    start_after: Option<ExpiringClawback>,
+    limit: Option<u32>,
+) -> StdResult<ExpiringResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    // a clawback is expired once the block is past its end, so the current
+    // height / time itself is excluded (height based ones are listed first)
+    let windows = [
+        (
+            Expiration::AtHeight(0),
+            Expiration::AtHeight(env.block.height),
+        ),
+        (Expiration::AtTime(0), Expiration::AtTime(env.block.time)),
+    ];
+    let mut items = vec![];
+    for (start, end) in windows.iter() {
+        if items.len() >= limit {
+            break;
+        }
+        // unwrap is safe, as neither is Never
+        let start = expiry_range_start(expiry_prefix(start).unwrap(), &start_after);
+        let end = expiry_prefix(end).unwrap();
+        items.extend(expiry_index_range(
+            &deps.storage,
+            &start,
+            &end,
+            limit - items.len(),
+        )?);
+    }
+    Ok(to_expiring_response(items))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::msg::HandleMsg::TopUp;
+    use crate::msg::{
+        ClawbackLimit, ExpiringClawback, ExpiringResponse, RefreshBy, ReleaseSchedule,
+    };
+    use cosmwasm_std::testing::{
+        mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MOCK_CONTRACT_ADDR,
+    };
+    use cosmwasm_std::{
+        coin, coins, CanonicalAddr, Coin, CosmosMsg, MemoryStorage, StdError, Uint128,
+    };
+    use cosmwasm_storage::bucket;
+    use cw0::{Duration, Expiration, NativeBalance};
+
+    use crate::state::{all_clawback_ids, ClawbackV0_3, PREFIX_CLAWBACK};
+
+    use super::*;
+
+    /// Calls the contract's handle and then checks that the total locked balance
+    /// still equals the sum of balances of all open clawbacks
+    fn handle<S: Storage, A: Api, Q: Querier>(
+        deps: &mut Extern<S, A, Q>,
+        env: Env,
+        info: MessageInfo,
+        msg: HandleMsg,
+    ) -> Result<HandleResponse, ContractError> {
+        let res = super::handle(deps, env, info, msg);
+        assert_total_locked_invariant(deps);
+        res
+    }
+
+    fn assert_total_locked_invariant<S: Storage, A: Api, Q: Querier>(deps: &Extern<S, A, Q>) {
+        let mut expected = GenericBalance::default();
+        for id in all_clawback_ids(&deps.storage).unwrap() {
+            let clawback = clawbacks_read(&deps.storage).load(id.as_bytes()).unwrap();
+            expected.add_balance(&clawback.balance);
+        }
+        let total = total_locked_read(&deps.storage)
+            .may_load()
+            .unwrap()
+            .unwrap_or_default();
+        assert_eq!(normalize(expected), normalize(total));
+    }
+
+    /// sorts the tokens and drops empty ones, so balances can be compared
+    fn normalize(mut balance: GenericBalance) -> GenericBalance {
+        balance.native.retain(|c| !c.amount.is_zero());
+        balance.native.sort_by(|a, b| a.denom.cmp(&b.denom));
+        balance.cw20.retain(|c| !c.amount.is_zero());
+        balance
+            .cw20
+            .sort_by(|a, b| a.address.as_slice().cmp(b.address.as_slice()));
+        balance
+    }
+
+    fn create_msg(id: &str) -> CreateMsg {
+        CreateMsg {
+            id: Some(id.to_string()),
+            backup: HumanAddr::from("backup"),
+            holder: HumanAddr::from("holder"),
+            clawback_period: Duration::Time(1),
+            cw20_whitelist: None,
+            backup_multisig: None,
+            refresh_policy: None,
+            release: None,
+            max_clawback: None,
+            arbiter: None,
+            depositors: None,
+            native_whitelist: None,
+            unlisted_denoms: None,
+        }
+    }
+
+    fn basic_native_setup() -> (
+        CreateMsg,
+        u64,
+        u64,
+        Extern<MemoryStorage, MockApi, MockQuerier>,
+        Vec<Coin>,
+    ) {
+        let mut deps = mock_dependencies(&[]);
+
+        // init an empty contract
+        let init_msg = InitMsg {
+            native_sink: Some(HumanAddr::from("community_pool")),
+            admin: Some(HumanAddr::from("admin")),
+            emergency_policy: None,
+            allowlist: None,
+            max_tokens: None,
+            peers: None,
+        };
+        let mock_clawback_period = 1;
+        let mock_time = 1571920875;
+        let mut init_env = mock_env();
+        init_env.block.time = mock_time;
+
+        let info = mock_info(HumanAddr::from("anyone"), &[]);
+        let res = init(&mut deps, init_env.clone(), info, init_msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // create a clawback
+        let create = create_msg("foobar");
+        let sender = HumanAddr::from("source");
+        let balance = coins(100, "tokens");
+        let info = mock_info(&sender, &balance);
+        let msg = HandleMsg::Create(create.clone());
+        let res = handle(&mut deps, init_env, info, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+        assert_eq!(attr("action", "create"), res.attributes[0]);
+
+        // ensure the details is what we expect
+        let details = query_details(&deps, mock_env(), "foobar".to_string()).unwrap();
+        assert_eq!(
+            details,
+            DetailsResponse {
+                id: "foobar".to_string(),
+                backup: HumanAddr::from("backup"),
+                holder: HumanAddr::from("holder"),
+                clawback_period: Duration::Time(mock_clawback_period),
+                end_time: Expiration::AtTime(mock_time + mock_clawback_period),
+                native_balance: balance.clone(),
+                cw20_balance: vec![],
+                cw20_whitelist: vec![],
+                backup_multisig: None,
+                pending_backup: None,
+                refresh_policy: RefreshPolicy::default(),
+                max_end_time: None,
+                refreshes: 0,
+                release: ReleaseSchedule::Cliff {},
+                max_clawback: None,
+                arbiter: None,
+                dispute: None,
+                depositors: DepositPolicy::Anyone {},
+                native_whitelist: None,
+                unlisted_denoms: UnlistedDenoms::Reject {},
+                clawed_back_native: vec![],
+                clawed_back_cw20: vec![],
+                vested_native: vec![],
+                vested_cw20: vec![],
+                unvested_native: balance.clone(),
+                unvested_cw20: vec![],
+            }
+        );
+        (create, mock_time, mock_clawback_period, deps, balance)
+    }
+
+    #[test]
+    fn happy_path_native() {
+        let (create, mock_time, mock_clawback_period, mut deps, balance) = basic_native_setup();
+
+        // withdraw it
+        let id = create.id.clone().unwrap();
+        let info = mock_info(&create.holder, &[]);
+        let mut new_env = mock_env();
+        new_env.block.time = mock_time + mock_clawback_period + 1;
+        let res = handle(
+            &mut deps,
+            new_env.clone(),
+            info,
+            HandleMsg::Withdraw {
+                id,
+                recipient: None,
+                msg: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_eq!(attr("action", "withdraw"), res.attributes[0]);
+        assert_eq!(
+            res.messages[0],
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: create.holder.clone(),
+                amount: balance,
+            })
+        );
+
+        // second attempt fails (not found)
+        let id = create.id.clone().unwrap();
+        let info = mock_info(&create.holder, &[]);
+        let res = handle(
+            &mut deps,
+            new_env,
+            info,
+            HandleMsg::Withdraw {
+                id,
+                recipient: None,
+                msg: None,
+            },
+        );
+        match res.unwrap_err() {
+            ContractError::Std(StdError::NotFound { .. }) => {}
+            e => panic!("Expected NotFound, got {}", e),
+        }
+    }
+
+    #[test]
+    fn happy_path_cw20() {
+        let mut deps = mock_dependencies(&[]);
+        let mock_time = 1571920875;
+        let mut init_env = mock_env();
+        init_env.block.time = mock_time;
+        // init an empty contract
+        let init_msg = InitMsg {
+            native_sink: None,
+            admin: None,
+            emergency_policy: None,
+            allowlist: None,
+            max_tokens: None,
+            peers: None,
+        };
+        let info = mock_info(HumanAddr::from("anyone"), &[]);
+
+        let res = init(&mut deps, init_env.clone(), info, init_msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // create a clawback
+        let mock_clawback_period = 1;
+        let create = CreateMsg {
+            cw20_whitelist: Some(vec![HumanAddr::from("other-token")]),
+            ..create_msg("foobar")
+        };
+        let receive = Cw20ReceiveMsg {
+            sender: HumanAddr::from("source"),
+            amount: Uint128(100),
+            msg: Some(to_binary(&HandleMsg::Create(create.clone())).unwrap()),
+        };
+        let token_contract = HumanAddr::from("my-cw20-token");
+        let info = mock_info(&token_contract, &[]);
+        let msg = HandleMsg::Receive(receive.clone());
+        let res = handle(&mut deps, init_env, info, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+        assert_eq!(attr("action", "create"), res.attributes[0]);
+        // ensure the whitelist is what we expect
+        let details = query_details(&deps, mock_env(), "foobar".to_string()).unwrap();
+
+        assert_eq!(
+            details,
+            DetailsResponse {
+                id: "foobar".to_string(),
+                holder: HumanAddr::from("holder"),
+                backup: HumanAddr::from("backup"),
+                end_time: Expiration::AtTime(mock_time + mock_clawback_period),
+                clawback_period: Duration::Time(mock_clawback_period),
+                native_balance: vec![],
+                cw20_balance: vec![Cw20CoinHuman {
+                    address: HumanAddr::from("my-cw20-token"),
+                    amount: Uint128(100),
+                }],
+                cw20_whitelist: vec![
+                    HumanAddr::from("other-token"),
+                    HumanAddr::from("my-cw20-token")
+                ],
+                backup_multisig: None,
+                pending_backup: None,
+                refresh_policy: RefreshPolicy::default(),
+                max_end_time: None,
+                refreshes: 0,
+                release: ReleaseSchedule::Cliff {},
+                max_clawback: None,
+                arbiter: None,
+                dispute: None,
+                depositors: DepositPolicy::Anyone {},
+                native_whitelist: None,
+                unlisted_denoms: UnlistedDenoms::Reject {},
+                clawed_back_native: vec![],
+                clawed_back_cw20: vec![],
+                vested_native: vec![],
+                vested_cw20: vec![],
+                unvested_native: vec![],
+                unvested_cw20: vec![Cw20CoinHuman {
+                    address: HumanAddr::from("my-cw20-token"),
+                    amount: Uint128(100),
+                }],
+            }
+        );
+
+        // withdraw it
+        let id = create.id.clone().unwrap();
+        let info = mock_info(&create.holder, &[]);
+        let mut new_env = mock_env();
+        new_env.block.time = mock_time + mock_clawback_period + 1;
+        let res = handle(
+            &mut deps,
+            new_env.clone(),
+            info,
+            HandleMsg::Withdraw {
+                id,
+                recipient: None,
+                msg: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_eq!(attr("action", "withdraw"), res.attributes[0]);
+        let send_msg = Cw20HandleMsg::Transfer {
+            recipient: create.holder.clone(),
+            amount: receive.amount,
+        };
+        assert_eq!(
+            res.messages[0],
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: token_contract,
+                msg: to_binary(&send_msg).unwrap(),
+                send: vec![],
+            })
+        );
+
+        // second attempt fails (not found)
+        let id = create.id.clone().unwrap();
+        let info = mock_info(&create.holder, &[]);
+        let res = handle(
+            &mut deps,
+            new_env,
+            info,
+            HandleMsg::Withdraw {
+                id,
+                recipient: None,
+                msg: None,
+            },
+        );
+        match res.unwrap_err() {
+            ContractError::Std(StdError::NotFound { .. }) => {}
+            e => panic!("Expected NotFound, got {}", e),
+        }
+    }
+
+    #[test]
+    fn remove_tokens_proper() {
+        let mut tokens = GenericBalance::default();
+        tokens.add_tokens(Balance::from(vec![coin(123, "atom"), coin(789, "eth")]));
+        assert_eq!(
+            tokens.remove_tokens(Balance::from(vec![coin(456, "atom"), coin(12, "btc")])),
+            Err(())
+        );
+        assert_eq!(tokens.native, vec![coin(123, "atom"), coin(789, "eth")]);
+        assert_eq!(
+            tokens.remove_tokens(Balance::from(vec![coin(1, "atom"), coin(1, "btc")])),
+            Err(())
+        );
+        assert_eq!(tokens.native, vec![coin(123, "atom"), coin(789, "eth")]);
+        assert_eq!(
+            tokens.remove_tokens(Balance::from(vec![coin(1, "atom"), coin(1, "eth")])),
+            Ok(())
+        );
+        assert_eq!(tokens.native, vec![coin(122, "atom"), coin(788, "eth")]);
+        // the same denom listed twice is removed twice
+        assert_eq!(
+            tokens.remove_tokens(Balance::from(vec![coin(400, "eth"), coin(400, "eth")])),
+            Err(())
+        );
+        assert_eq!(tokens.native, vec![coin(122, "atom"), coin(788, "eth")]);
+        // emptied tokens are dropped
+        assert_eq!(
+            tokens.remove_tokens(Balance::from(vec![coin(61, "atom"), coin(61, "atom")])),
+            Ok(())
+        );
+        assert_eq!(tokens.native, vec![coin(788, "eth")]);
+    }
+
+    #[test]
+    fn remove_cw_tokens_proper() {
+        let mut tokens = GenericBalance::default();
+        let bar_token = CanonicalAddr(b"bar_token".to_vec().into());
+        let foo_token = CanonicalAddr(b"foo_token".to_vec().into());
+        tokens.add_tokens(Balance::Cw20(Cw20Coin {
+            address: foo_token.clone(),
+            amount: Uint128(12345),
+        }));
+        assert_eq!(
+            tokens.remove_tokens(Balance::Cw20(Cw20Coin {
+                address: bar_token.clone(),
+                amount: Uint128(777),
+            })),
+            Err(())
+        );
+        assert_eq!(
+            tokens.cw20,
+            vec![Cw20Coin {
+                address: foo_token.clone(),
+                amount: Uint128(12345),
+            }]
+        );
+        assert_eq!(
+            tokens.remove_tokens(Balance::Cw20(Cw20Coin {
+                address: foo_token.clone(),
+                amount: Uint128(23400),
+            })),
+            Err(())
+        );
+        assert_eq!(
+            tokens.cw20,
+            vec![Cw20Coin {
+                address: foo_token.clone(),
+                amount: Uint128(12345),
+            }]
+        );
+        assert_eq!(
+            tokens.remove_tokens(Balance::Cw20(Cw20Coin {
+                address: foo_token.clone(),
+                amount: Uint128(1),
+            })),
+            Ok(())
+        );
+        assert_eq!(
+            tokens.cw20,
+            vec![Cw20Coin {
+                address: foo_token.clone(),
+                amount: Uint128(12344),
+            }]
+        );
+    }
+
+    #[test]
+    fn add_tokens_proper() {
+        let mut tokens = GenericBalance::default();
+        tokens.add_tokens(Balance::from(vec![coin(123, "atom"), coin(789, "eth")]));
+        tokens.add_tokens(Balance::from(vec![coin(456, "atom"), coin(12, "btc")]));
+        assert_eq!(
+            tokens.native,
+            vec![coin(579, "atom"), coin(789, "eth"), coin(12, "btc")]
+        );
+    }
+
+    #[test]
+    fn add_cw_tokens_proper() {
+        let mut tokens = GenericBalance::default();
+        let bar_token = CanonicalAddr(b"bar_token".to_vec().into());
+        let foo_token = CanonicalAddr(b"foo_token".to_vec().into());
+        tokens.add_tokens(Balance::Cw20(Cw20Coin {
+            address: foo_token.clone(),
+            amount: Uint128(12345),
+        }));
+        tokens.add_tokens(Balance::Cw20(Cw20Coin {
+            address: bar_token.clone(),
+            amount: Uint128(777),
+        }));
+        tokens.add_tokens(Balance::Cw20(Cw20Coin {
+            address: foo_token.clone(),
+            amount: Uint128(23400),
+        }));
+        assert_eq!(
+            tokens.cw20,
+            vec![
+                Cw20Coin {
+                    address: foo_token,
+                    amount: Uint128(35745),
+                },
+                Cw20Coin {
+                    address: bar_token,
+                    amount: Uint128(777),
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn transfer_cw20() {
+        let mut deps = mock_dependencies(&[]);
+
+        // init an empty contract
+        let init_msg = InitMsg {
+            native_sink: None,
+            admin: None,
+            emergency_policy: None,
+            allowlist: None,
+            max_tokens: None,
+            peers: None,
+        };
+        let mock_clawback_period = 2;
+        let mock_time = 1571920875;
+        let mut init_env = mock_env();
+        init_env.block.time = mock_time;
+        let token_contract = HumanAddr::from("my-cw20-token");
+
+        let info = mock_info(HumanAddr::from("anyone"), &[]);
+        let res = init(&mut deps, init_env.clone(), info, init_msg).unwrap();
+        assert_eq!(0, res.messages.len());
+        // create two clawbacks
+        for idc in ["foo", "bar", "wrong-per", "wrong-back"].iter() {
+            let create = CreateMsg {
+                backup: if *idc == "wrong-back" {
+                    HumanAddr::from("backup2")
+                } else {
+                    HumanAddr::from("backup")
+                },
+                holder: HumanAddr::from(*idc),
+                clawback_period: Duration::Time(if *idc == "wrong-per" {
+                    mock_clawback_period - 1
+                } else {
+                    mock_clawback_period
+                }),
+                cw20_whitelist: Some(vec![HumanAddr::from("other-token")]),
+                ..create_msg(idc)
+            };
+            let receive = Cw20ReceiveMsg {
+                sender: HumanAddr::from("source"),
+                amount: Uint128(100),
+                msg: Some(to_binary(&HandleMsg::Create(create.clone())).unwrap()),
+            };
+
+            let info = mock_info(&token_contract, &[]);
+            let msg = HandleMsg::Receive(receive.clone());
+
+            let res = handle(&mut deps, init_env.clone(), info, msg).unwrap();
+            assert_eq!(0, res.messages.len());
+            assert_eq!(attr("action", "create"), res.attributes[0]);
+
+            // ensure the details is what we expect
+            let details = query_details(&deps, mock_env(), idc.to_string()).unwrap();
+            assert_eq!(
+                details,
+                DetailsResponse {
+                    id: idc.to_string(),
+                    backup: if *idc == "wrong-back" {
+                        HumanAddr::from("backup2")
+                    } else {
+                        HumanAddr::from("backup")
+                    },
+                    holder: HumanAddr::from(*idc),
+                    clawback_period: Duration::Time(if *idc == "wrong-per" {
+                        mock_clawback_period - 1
+                    } else {
+                        mock_clawback_period
+                    }),
+                    end_time: Expiration::AtTime(if *idc == "wrong-per" {
+                        mock_time + mock_clawback_period - 1
+                    } else {
+                        mock_time + mock_clawback_period
+                    }),
+                    native_balance: vec![],
+                    cw20_balance: vec![Cw20CoinHuman {
+                        address: HumanAddr::from("my-cw20-token"),
+                        amount: Uint128(100),
+                    }],
+                    cw20_whitelist: vec![
+                        HumanAddr::from("other-token"),
+                        HumanAddr::from("my-cw20-token")
+                    ],
+                    backup_multisig: None,
+                    pending_backup: None,
+                    refresh_policy: RefreshPolicy::default(),
+                    max_end_time: None,
+                    refreshes: 0,
+                    release: ReleaseSchedule::Cliff {},
+                    max_clawback: None,
+                    arbiter: None,
+                    dispute: None,
+                    depositors: DepositPolicy::Anyone {},
+                    native_whitelist: None,
+                    unlisted_denoms: UnlistedDenoms::Reject {},
+                    clawed_back_native: vec![],
+                    clawed_back_cw20: vec![],
+                    vested_native: vec![],
+                    vested_cw20: vec![],
+                    unvested_native: vec![],
+                    unvested_cw20: vec![Cw20CoinHuman {
+                        address: HumanAddr::from("my-cw20-token"),
+                        amount: Uint128(100),
+                    }],
+                }
+            );
+        }
+
+        // transfer it
+        let from_id = "foo".to_string();
+        let to_id = "bar".to_string();
+        let info = mock_info(HumanAddr::from(from_id.clone()), &[]);
+        let mut new_env = mock_env();
+        new_env.block.time = mock_time + 1;
+        let token_address = deps
+            .api
+            .canonical_address(&HumanAddr::from("my-cw20-token"))
+            .unwrap();
+        let res = handle(
+            &mut deps,
+            new_env.clone(),
+            info,
+            HandleMsg::ClawbackTransfer {
+                from_id,
+                to_id,
+                amount: Balance::Cw20(Cw20Coin {
+                    address: token_address.clone(),
+                    amount: Uint128(1),
+                }),
+                create: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(0, res.messages.len());
+        assert_eq!(attr("action", "transfer"), res.attributes[0]);
+
+        // ensure the details is what we expect
+        let details = query_details(&deps, mock_env(), "foo".to_string()).unwrap();
+        assert_eq!(
+            details,
+            DetailsResponse {
+                id: "foo".to_string(),
+                backup: HumanAddr::from("backup"),
+                holder: HumanAddr::from("foo"),
+                clawback_period: Duration::Time(mock_clawback_period),
+                end_time: Expiration::AtTime(mock_time + mock_clawback_period),
+                native_balance: vec![],
+                cw20_balance: vec![Cw20CoinHuman {
+                    address: HumanAddr::from("my-cw20-token"),
+                    amount: Uint128(99),
+                }],
+                cw20_whitelist: vec![
+                    HumanAddr::from("other-token"),
+                    HumanAddr::from("my-cw20-token")
+                ],
+                backup_multisig: None,
+                pending_backup: None,
+                refresh_policy: RefreshPolicy::default(),
+                max_end_time: None,
+                refreshes: 0,
+                release: ReleaseSchedule::Cliff {},
+                max_clawback: None,
+                arbiter: None,
+                dispute: None,
+                depositors: DepositPolicy::Anyone {},
+                native_whitelist: None,
+                unlisted_denoms: UnlistedDenoms::Reject {},
+                clawed_back_native: vec![],
+                clawed_back_cw20: vec![],
+                vested_native: vec![],
+                vested_cw20: vec![],
+                unvested_native: vec![],
+                unvested_cw20: vec![Cw20CoinHuman {
+                    address: HumanAddr::from("my-cw20-token"),
+                    amount: Uint128(99),
+                }],
+            }
+        );
+
+        let details = query_details(&deps, mock_env(), "bar".to_string()).unwrap();
+        assert_eq!(
+            details,
+            DetailsResponse {
+                id: "bar".to_string(),
+                backup: HumanAddr::from("backup"),
+                holder: HumanAddr::from("bar"),
+                clawback_period: Duration::Time(mock_clawback_period),
+                end_time: Expiration::AtTime(mock_time + 1 + mock_clawback_period),
+                native_balance: vec![],
+                cw20_balance: vec![Cw20CoinHuman {
+                    address: HumanAddr::from("my-cw20-token"),
+                    amount: Uint128(101),
+                }],
+                cw20_whitelist: vec![
+                    HumanAddr::from("other-token"),
+                    HumanAddr::from("my-cw20-token")
+                ],
+                backup_multisig: None,
+                pending_backup: None,
+                refresh_policy: RefreshPolicy::default(),
+                max_end_time: None,
+                refreshes: 1,
+                release: ReleaseSchedule::Cliff {},
+                max_clawback: None,
+                arbiter: None,
+                dispute: None,
+                depositors: DepositPolicy::Anyone {},
+                native_whitelist: None,
+                unlisted_denoms: UnlistedDenoms::Reject {},
+                clawed_back_native: vec![],
+                clawed_back_cw20: vec![],
+                vested_native: vec![],
+                vested_cw20: vec![],
+                unvested_native: vec![],
+                unvested_cw20: vec![Cw20CoinHuman {
+                    address: HumanAddr::from("my-cw20-token"),
+                    amount: Uint128(101),
+                }],
+            }
+        );
+
+        // claw back
+        let from_id = "bar".to_string();
+        let to_id = "foo".to_string();
+        let info = mock_info(HumanAddr::from("backup"), &[]);
+        let res = handle(
+            &mut deps,
+            new_env.clone(),
+            info,
+            HandleMsg::ClawbackTransfer {
+                from_id,
+                to_id,
+                amount: Balance::Cw20(Cw20Coin {
+                    address: token_address.clone(),
+                    amount: Uint128(1),
+                }),
+                create: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(0, res.messages.len());
+        assert_eq!(attr("action", "transfer"), res.attributes[0]);
+
+        // ensure the details is what we expect
+        let details = query_details(&deps, mock_env(), "foo".to_string()).unwrap();
+        assert_eq!(
+            details,
+            DetailsResponse {
+                id: "foo".to_string(),
+                backup: HumanAddr::from("backup"),
+                holder: HumanAddr::from("foo"),
+                clawback_period: Duration::Time(mock_clawback_period),
+                end_time: Expiration::AtTime(mock_time + 1 + mock_clawback_period),
+                native_balance: vec![],
+                cw20_balance: vec![Cw20CoinHuman {
+                    address: HumanAddr::from("my-cw20-token"),
+                    amount: Uint128(100),
+                }],
+                cw20_whitelist: vec![
+                    HumanAddr::from("other-token"),
+                    HumanAddr::from("my-cw20-token")
+                ],
+                backup_multisig: None,
+                pending_backup: None,
+                refresh_policy: RefreshPolicy::default(),
+                max_end_time: None,
+                refreshes: 1,
+                release: ReleaseSchedule::Cliff {},
+                max_clawback: None,
+                arbiter: None,
+                dispute: None,
+                depositors: DepositPolicy::Anyone {},
+                native_whitelist: None,
+                unlisted_denoms: UnlistedDenoms::Reject {},
+                clawed_back_native: vec![],
+                clawed_back_cw20: vec![],
+                vested_native: vec![],
+                vested_cw20: vec![],
+                unvested_native: vec![],
+                unvested_cw20: vec![Cw20CoinHuman {
+                    address: HumanAddr::from("my-cw20-token"),
+                    amount: Uint128(100),
+                }],
+            }
+        );
+
+        let details = query_details(&deps, mock_env(), "bar".to_string()).unwrap();
+        assert_eq!(
+            details,
+            DetailsResponse {
+                id: "bar".to_string(),
+                backup: HumanAddr::from("backup"),
+                holder: HumanAddr::from("bar"),
+                clawback_period: Duration::Time(mock_clawback_period),
+                end_time: Expiration::AtTime(mock_time + 1 + mock_clawback_period),
+                native_balance: vec![],
+                cw20_balance: vec![Cw20CoinHuman {
+                    address: HumanAddr::from("my-cw20-token"),
+                    amount: Uint128(100),
+                }],
+                cw20_whitelist: vec![
+                    HumanAddr::from("other-token"),
+                    HumanAddr::from("my-cw20-token")
+                ],
+                backup_multisig: None,
+                pending_backup: None,
+                refresh_policy: RefreshPolicy::default(),
+                max_end_time: None,
+                refreshes: 1,
+                release: ReleaseSchedule::Cliff {},
+                max_clawback: None,
+                arbiter: None,
+                dispute: None,
+                depositors: DepositPolicy::Anyone {},
+                native_whitelist: None,
+                unlisted_denoms: UnlistedDenoms::Reject {},
+                clawed_back_native: vec![],
+                clawed_back_cw20: vec![Cw20CoinHuman {
+                    address: HumanAddr::from("my-cw20-token"),
+                    amount: Uint128(1)
+                }],
+                vested_native: vec![],
+                vested_cw20: vec![],
+                unvested_native: vec![],
+                unvested_cw20: vec![Cw20CoinHuman {
+                    address: HumanAddr::from("my-cw20-token"),
+                    amount: Uint128(100),
+                }],
+            }
+        );
+
+        // failures
+        let from_id = "foo".to_string();
+        let to_id = "wrong-per".to_string();
+        let info = mock_info(HumanAddr::from(from_id.clone()), &[]);
+        let res = handle(
+            &mut deps,
+            new_env.clone(),
+            info,
+            HandleMsg::ClawbackTransfer {
+                from_id,
+                to_id,
+                amount: Balance::Cw20(Cw20Coin {
+                    address: token_address.clone(),
+                    amount: Uint128(1),
+                }),
+                create: None,
+            },
+        );
+
+        match res.unwrap_err() {
+            ContractError::ContractMismatch {} => {}
+            e => panic!("Expected ContractMismatch, got {}", e),
+        }
+
+        let from_id = "foo".to_string();
+        let to_id = "wrong-back".to_string();
+        let info = mock_info(HumanAddr::from(from_id.clone()), &[]);
+        let res = handle(
+            &mut deps,
+            new_env.clone(),
+            info,
+            HandleMsg::ClawbackTransfer {
+                from_id,
+                to_id,
+                amount: Balance::Cw20(Cw20Coin {
+                    address: token_address.clone(),
+                    amount: Uint128(1),
+                }),
+                create: None,
+            },
+        );
+
+        match res.unwrap_err() {
+            ContractError::ContractMismatch {} => {}
+            e => panic!("Expected ContractMismatch, got {}", e),
+        }
+    }
+
+    #[test]
+    fn transfer_native() {
+        let mut deps = mock_dependencies(&[]);
+
+        // init an empty contract
+        let init_msg = InitMsg {
+            native_sink: None,
+            admin: None,
+            emergency_policy: None,
+            allowlist: None,
+            max_tokens: None,
+            peers: None,
+        };
+        let mock_clawback_period = 2;
+        let mock_time = 1571920875;
+        let mut init_env = mock_env();
+        init_env.block.time = mock_time;
+
+        let info = mock_info(HumanAddr::from("anyone"), &[]);
+        let res = init(&mut deps, init_env.clone(), info, init_msg).unwrap();
+        assert_eq!(0, res.messages.len());
+        let balance = coins(100, "tokens");
+        // create two clawbacks
+        for idc in ["foo", "bar", "wrong-per", "wrong-back"].iter() {
+            let create = CreateMsg {
+                backup: if *idc == "wrong-back" {
+                    HumanAddr::from("backup2")
+                } else {
+                    HumanAddr::from("backup")
+                },
+                holder: HumanAddr::from(*idc),
+                clawback_period: Duration::Time(if *idc == "wrong-per" {
+                    mock_clawback_period - 1
+                } else {
+                    mock_clawback_period
+                }),
+                ..create_msg(idc)
+            };
+            let sender = HumanAddr::from("source");
+
+            let info = mock_info(&sender, &balance);
+            let msg = HandleMsg::Create(create.clone());
+
+            let res = handle(&mut deps, init_env.clone(), info, msg).unwrap();
+            assert_eq!(0, res.messages.len());
+            assert_eq!(attr("action", "create"), res.attributes[0]);
+
+            // ensure the details is what we expect
+            let details = query_details(&deps, mock_env(), idc.to_string()).unwrap();
+            assert_eq!(
+                details,
+                DetailsResponse {
+                    id: idc.to_string(),
+                    backup: if *idc == "wrong-back" {
+                        HumanAddr::from("backup2")
+                    } else {
+                        HumanAddr::from("backup")
+                    },
+                    holder: HumanAddr::from(*idc),
+                    clawback_period: Duration::Time(if *idc == "wrong-per" {
+                        mock_clawback_period - 1
+                    } else {
+                        mock_clawback_period
+                    }),
+                    end_time: Expiration::AtTime(if *idc == "wrong-per" {
+                        mock_time + mock_clawback_period - 1
+                    } else {
+                        mock_time + mock_clawback_period
+                    }),
+                    native_balance: balance.clone(),
+                    cw20_balance: vec![],
+                    cw20_whitelist: vec![],
+                    backup_multisig: None,
+                    pending_backup: None,
+                    refresh_policy: RefreshPolicy::default(),
+                    max_end_time: None,
+                    refreshes: 0,
+                    release: ReleaseSchedule::Cliff {},
+                    max_clawback: None,
+                    arbiter: None,
+                    dispute: None,
+                    depositors: DepositPolicy::Anyone {},
+                    native_whitelist: None,
+                    unlisted_denoms: UnlistedDenoms::Reject {},
+                    clawed_back_native: vec![],
+                    clawed_back_cw20: vec![],
+                    vested_native: vec![],
+                    vested_cw20: vec![],
+                    unvested_native: balance.clone(),
+                    unvested_cw20: vec![],
+                }
+            );
+        }
+
+        // transfer it
+        let from_id = "foo".to_string();
+        let to_id = "bar".to_string();
+        let info = mock_info(HumanAddr::from(from_id.clone()), &[]);
+        let mut new_env = mock_env();
+        new_env.block.time = mock_time + 1;
+        let res = handle(
+            &mut deps,
+            new_env.clone(),
+            info,
+            HandleMsg::ClawbackTransfer {
+                from_id,
+                to_id,
+                amount: Balance::Native(NativeBalance(coins(1, "tokens"))),
+                create: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(0, res.messages.len());
+        assert_eq!(attr("action", "transfer"), res.attributes[0]);
+
+        // ensure the details is what we expect
+        let details = query_details(&deps, mock_env(), "foo".to_string()).unwrap();
+        assert_eq!(
+            details,
+            DetailsResponse {
+                id: "foo".to_string(),
+                backup: HumanAddr::from("backup"),
+                holder: HumanAddr::from("foo"),
+                clawback_period: Duration::Time(mock_clawback_period),
+                end_time: Expiration::AtTime(mock_time + mock_clawback_period),
+                native_balance: coins(99, "tokens"),
+                cw20_balance: vec![],
+                cw20_whitelist: vec![],
+                backup_multisig: None,
+                pending_backup: None,
+                refresh_policy: RefreshPolicy::default(),
+                max_end_time: None,
+                refreshes: 0,
+                release: ReleaseSchedule::Cliff {},
+                max_clawback: None,
+                arbiter: None,
+                dispute: None,
+                depositors: DepositPolicy::Anyone {},
+                native_whitelist: None,
+                unlisted_denoms: UnlistedDenoms::Reject {},
+                clawed_back_native: vec![],
+                clawed_back_cw20: vec![],
+                vested_native: vec![],
+                vested_cw20: vec![],
+                unvested_native: coins(99, "tokens"),
+                unvested_cw20: vec![],
+            }
+        );
+
+        let details = query_details(&deps, mock_env(), "bar".to_string()).unwrap();
+        assert_eq!(
+            details,
+            DetailsResponse {
+                id: "bar".to_string(),
+                backup: HumanAddr::from("backup"),
+                holder: HumanAddr::from("bar"),
+                clawback_period: Duration::Time(mock_clawback_period),
+                end_time: Expiration::AtTime(mock_time + 1 + mock_clawback_period),
+                native_balance: coins(101, "tokens"),
+                cw20_balance: vec![],
+                cw20_whitelist: vec![],
+                backup_multisig: None,
+                pending_backup: None,
+                refresh_policy: RefreshPolicy::default(),
+                max_end_time: None,
+                refreshes: 1,
+                release: ReleaseSchedule::Cliff {},
+                max_clawback: None,
+                arbiter: None,
+                dispute: None,
+                depositors: DepositPolicy::Anyone {},
+                native_whitelist: None,
+                unlisted_denoms: UnlistedDenoms::Reject {},
+                clawed_back_native: vec![],
+                clawed_back_cw20: vec![],
+                vested_native: vec![],
+                vested_cw20: vec![],
+                unvested_native: coins(101, "tokens"),
+                unvested_cw20: vec![],
+            }
+        );
+
+        // claw back
+        let from_id = "bar".to_string();
+        let to_id = "foo".to_string();
+        let info = mock_info(HumanAddr::from("backup"), &[]);
+        let res = handle(
+            &mut deps,
+            new_env.clone(),
+            info,
+            HandleMsg::ClawbackTransfer {
+                from_id,
+                to_id,
+                amount: Balance::Native(NativeBalance(coins(1, "tokens"))),
+                create: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(0, res.messages.len());
+        assert_eq!(attr("action", "transfer"), res.attributes[0]);
+
+        // ensure the details is what we expect
+        let details = query_details(&deps, mock_env(), "foo".to_string()).unwrap();
+        assert_eq!(
+            details,
+            DetailsResponse {
+                id: "foo".to_string(),
+                backup: HumanAddr::from("backup"),
+                holder: HumanAddr::from("foo"),
+                clawback_period: Duration::Time(mock_clawback_period),
+                end_time: Expiration::AtTime(mock_time + 1 + mock_clawback_period),
+                native_balance: balance.clone(),
+                cw20_balance: vec![],
+                cw20_whitelist: vec![],
+                backup_multisig: None,
+                pending_backup: None,
+                refresh_policy: RefreshPolicy::default(),
+                max_end_time: None,
+                refreshes: 1,
+                release: ReleaseSchedule::Cliff {},
+                max_clawback: None,
+                arbiter: None,
+                dispute: None,
+                depositors: DepositPolicy::Anyone {},
+                native_whitelist: None,
+                unlisted_denoms: UnlistedDenoms::Reject {},
+                clawed_back_native: vec![],
+                clawed_back_cw20: vec![],
+                vested_native: vec![],
+                vested_cw20: vec![],
+                unvested_native: balance.clone(),
+                unvested_cw20: vec![],
+            }
+        );
+
+        let details = query_details(&deps, mock_env(), "bar".to_string()).unwrap();
+        assert_eq!(
+            details,
+            DetailsResponse {
+                id: "bar".to_string(),
+                backup: HumanAddr::from("backup"),
+                holder: HumanAddr::from("bar"),
+                clawback_period: Duration::Time(mock_clawback_period),
+                end_time: Expiration::AtTime(mock_time + 1 + mock_clawback_period),
+                native_balance: balance.clone(),
+                cw20_balance: vec![],
+                cw20_whitelist: vec![],
+                backup_multisig: None,
+                pending_backup: None,
+                refresh_policy: RefreshPolicy::default(),
+                max_end_time: None,
+                refreshes: 1,
+                release: ReleaseSchedule::Cliff {},
+                max_clawback: None,
+                arbiter: None,
+                dispute: None,
+                depositors: DepositPolicy::Anyone {},
+                native_whitelist: None,
+                unlisted_denoms: UnlistedDenoms::Reject {},
+                clawed_back_native: coins(1, "tokens"),
+                clawed_back_cw20: vec![],
+                vested_native: vec![],
+                vested_cw20: vec![],
+                unvested_native: balance.clone(),
+                unvested_cw20: vec![],
+            }
+        );
+
+        // failures
+        let from_id = "foo".to_string();
+        let to_id = "wrong-per".to_string();
+        let info = mock_info(HumanAddr::from(from_id.clone()), &[]);
+        let res = handle(
+            &mut deps,
+            new_env.clone(),
+            info,
+            HandleMsg::ClawbackTransfer {
+                from_id,
+                to_id,
+                amount: Balance::Native(NativeBalance(coins(1, "tokens"))),
+                create: None,
+            },
+        );
+
+        match res.unwrap_err() {
+            ContractError::ContractMismatch {} => {}
+            e => panic!("Expected ContractMismatch, got {}", e),
+        }
+
+        let from_id = "foo".to_string();
+        let to_id = "wrong-back".to_string();
+        let info = mock_info(HumanAddr::from(from_id.clone()), &[]);
+        let res = handle(
+            &mut deps,
+            new_env.clone(),
+            info,
+            HandleMsg::ClawbackTransfer {
+                from_id,
+                to_id,
+                amount: Balance::Native(NativeBalance(coins(1, "tokens"))),
+                create: None,
+            },
+        );
+
+        match res.unwrap_err() {
+            ContractError::ContractMismatch {} => {}
+            e => panic!("Expected ContractMismatch, got {}", e),
+        }
+
+        // a clawback cannot transfer to itself
+        let info = mock_info(HumanAddr::from("foo"), &[]);
+        let res = handle(
+            &mut deps,
+            new_env.clone(),
+            info,
+            HandleMsg::ClawbackTransfer {
+                from_id: "foo".to_string(),
+                to_id: "foo".to_string(),
+                amount: Balance::Native(NativeBalance(coins(1, "tokens"))),
+                create: None,
+            },
+        );
+
+        match res.unwrap_err() {
+            ContractError::ContractMismatch {} => {}
+            e => panic!("Expected ContractMismatch, got {}", e),
+        }
+        let details = query_details(&deps, mock_env(), "foo".to_string()).unwrap();
+        assert_eq!(details.native_balance, balance);
+        assert_total_locked_invariant(&deps);
+    }
+
+    #[test]
+    fn refresh() {
+        let (create, mock_time, mock_clawback_period, mut deps, balance) = basic_native_setup();
+
+        // refresh it
+        let id = create.id.clone().unwrap();
+        let info = mock_info(&create.holder, &[]);
+        let mut new_env = mock_env();
+        new_env.block.time = mock_time + mock_clawback_period;
+        let res = handle(
+            &mut deps,
+            new_env.clone(),
+            info,
+            HandleMsg::Refresh { id: id.clone() },
+        )
+        .unwrap();
+        assert_eq!(0, res.messages.len());
+        assert_eq!(attr("action", "refresh"), res.attributes[0]);
+        let details = query_details(&deps, mock_env(), id.clone()).unwrap();
+        assert_eq!(
+            details,
+            DetailsResponse {
+                id,
+                backup: HumanAddr::from("backup"),
+                holder: HumanAddr::from("holder"),
+                clawback_period: Duration::Time(mock_clawback_period),
+                end_time: Expiration::AtTime(mock_time + 2 * mock_clawback_period),
+                native_balance: balance.clone(),
+                cw20_balance: vec![],
+                cw20_whitelist: vec![],
+                backup_multisig: None,
+                pending_backup: None,
+                refresh_policy: RefreshPolicy::default(),
+                max_end_time: None,
+                refreshes: 1,
+                release: ReleaseSchedule::Cliff {},
+                max_clawback: None,
+                arbiter: None,
+                dispute: None,
+                depositors: DepositPolicy::Anyone {},
+                native_whitelist: None,
+                unlisted_denoms: UnlistedDenoms::Reject {},
+                clawed_back_native: vec![],
+                clawed_back_cw20: vec![],
+                vested_native: vec![],
+                vested_cw20: vec![],
+                unvested_native: balance.clone(),
+                unvested_cw20: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn burn() {
+        let (create, mock_time, _, mut deps, balance) = basic_native_setup();
+
+        // burn it
+        let id = create.id.clone().unwrap();
+        let info = mock_info(&create.backup, &[]);
+        let mut new_env = mock_env();
+        new_env.block.time = mock_time;
+        let res = handle(&mut deps, new_env.clone(), info, HandleMsg::Burn { id }).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_eq!(attr("action", "burn"), res.attributes[0]);
+        // native tokens go to the configured sink
+        assert_eq!(
+            res.messages[0],
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("community_pool"),
+                amount: balance,
+            })
+        );
+
+        // second attempt fails (not found)
+        let id = create.id.clone().unwrap();
+        let info = mock_info(&create.backup, &[]);
+        let res = handle(&mut deps, new_env, info, HandleMsg::Burn { id });
+        match res.unwrap_err() {
+            ContractError::Std(StdError::NotFound { .. }) => {}
+            e => panic!("Expected NotFound, got {}", e),
+        }
+    }
+
+    #[test]
+    fn burn_cw20_and_native_without_sink() {
+        let mut deps = mock_dependencies(&[]);
+        let mock_time = 1571920875;
+        let mut init_env = mock_env();
+        init_env.block.time = mock_time;
+        // init a contract without a native sink
+        let init_msg = InitMsg {
+            native_sink: None,
+            admin: None,
+            emergency_policy: None,
+            allowlist: None,
+            max_tokens: None,
+            peers: None,
+        };
+        let info = mock_info(HumanAddr::from("anyone"), &[]);
+        init(&mut deps, init_env.clone(), info, init_msg).unwrap();
+
+        // create a clawback with cw20 tokens
+        let create = create_msg("foobar");
+        let receive = Cw20ReceiveMsg {
+            sender: HumanAddr::from("source"),
+            amount: Uint128(100),
+            msg: Some(to_binary(&HandleMsg::Create(create.clone())).unwrap()),
+        };
+        let token_contract = HumanAddr::from("my-cw20-token");
+        let info = mock_info(&token_contract, &[]);
+        handle(
+            &mut deps,
+            init_env.clone(),
+            info,
+            HandleMsg::Receive(receive),
+        )
+        .unwrap();
+
+        // top it up with native tokens
+        let info = mock_info(HumanAddr::from("source"), &coins(50, "tokens"));
+        let top_up = HandleMsg::TopUp {
+            id: create.id.clone().unwrap(),
+        };
+        handle(&mut deps, init_env.clone(), info, top_up).unwrap();
+
+        // native tokens cannot be burned without a sink
+        let info = mock_info(&create.backup, &[]);
+        let burn = HandleMsg::Burn {
+            id: create.id.clone().unwrap(),
+        };
+        let res = handle(&mut deps, init_env.clone(), info, burn.clone());
+        match res.unwrap_err() {
+            ContractError::NoNativeSink {} => {}
+            e => panic!("Expected NoNativeSink, got {}", e),
+        }
+
+        // a clawback with only cw20 tokens burns them
+        let create = CreateMsg {
+            id: Some("cw20only".to_string()),
+            ..create
+        };
+        let receive = Cw20ReceiveMsg {
+            sender: HumanAddr::from("source"),
+            amount: Uint128(100),
+            msg: Some(to_binary(&HandleMsg::Create(create.clone())).unwrap()),
+        };
+        let info = mock_info(&token_contract, &[]);
+        handle(
+            &mut deps,
+            init_env.clone(),
+            info,
+            HandleMsg::Receive(receive),
+        )
+        .unwrap();
+
+        let info = mock_info(&create.backup, &[]);
+        let burn = HandleMsg::Burn {
+            id: create.id.clone().unwrap(),
+        };
+        let res = handle(&mut deps, init_env, info, burn).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_eq!(attr("action", "burn"), res.attributes[0]);
+        assert_eq!(attr("id", "cw20only"), res.attributes[1]);
+        assert_eq!(
+            res.messages[0],
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: token_contract,
+                msg: to_binary(&Cw20HandleMsg::Burn {
+                    amount: Uint128(100)
+                })
+                .unwrap(),
+                send: vec![],
+            })
+        );
+    }
+
+    #[test]
+    fn top_up_mixed_tokens() {
+        let mut deps = mock_dependencies(&[]);
+        let mock_time = 1571920875;
+        let mut init_env = mock_env();
         init_env.block.time = mock_time;
         // init an empty contract
-        let init_msg = InitMsg {};
-        let info = mock_info(&HumanAddr::from("anyone"), &[]);
+        let init_msg = InitMsg {
+            native_sink: None,
+            admin: None,
+            emergency_policy: None,
+            allowlist: None,
+            max_tokens: None,
+            peers: None,
+        };
+        let info = mock_info(HumanAddr::from("anyone"), &[]);
+        let res = init(&mut deps, init_env.clone(), info, init_msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // only accept these tokens
+        let whitelist = vec![HumanAddr::from("bar_token"), HumanAddr::from("foo_token")];
+
+        // create a clawback with 2 native tokens
+        let mock_clawback_period = 1;
+
+        let create = CreateMsg {
+            cw20_whitelist: Some(whitelist),
+            ..create_msg("foobar")
+        };
+        let sender = HumanAddr::from("source");
+        let balance = vec![coin(100, "fee"), coin(200, "stake")];
+        let info = mock_info(&sender, &balance);
+        let msg = HandleMsg::Create(create.clone());
+        let res = handle(&mut deps, init_env.clone(), info, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+        assert_eq!(attr("action", "create"), res.attributes[0]);
+
+        // top it up with 2 more native tokens
+        let extra_native = vec![coin(250, "random"), coin(300, "stake")];
+        let info = mock_info(&sender, &extra_native);
+        let top_up = HandleMsg::TopUp {
+            id: create.id.clone().unwrap(),
+        };
+        let res = handle(&mut deps, init_env.clone(), info, top_up).unwrap();
+        assert_eq!(0, res.messages.len());
+        assert_eq!(attr("action", "top_up"), res.attributes[0]);
+
+        // top up with one foreign token
+        let bar_token = HumanAddr::from("bar_token");
+        let base = TopUp {
+            id: create.id.clone().unwrap(),
+        };
+        let top_up = HandleMsg::Receive(Cw20ReceiveMsg {
+            sender: HumanAddr::from("random"),
+            amount: Uint128(7890),
+            msg: Some(to_binary(&base).unwrap()),
+        });
+        let info = mock_info(&bar_token, &[]);
+        let res = handle(&mut deps, init_env.clone(), info, top_up).unwrap();
+        assert_eq!(0, res.messages.len());
+        assert_eq!(attr("action", "top_up"), res.attributes[0]);
+
+        // top with a foreign token not on the whitelist
+        // top up with one foreign token
+        let baz_token = HumanAddr::from("baz_token");
+        let base = TopUp {
+            id: create.id.clone().unwrap(),
+        };
+        let top_up = HandleMsg::Receive(Cw20ReceiveMsg {
+            sender: HumanAddr::from("random"),
+            amount: Uint128(7890),
+            msg: Some(to_binary(&base).unwrap()),
+        });
+        let info = mock_info(&baz_token, &[]);
+        let res = handle(&mut deps, init_env.clone(), info, top_up);
+        match res.unwrap_err() {
+            ContractError::NotInWhitelist {} => {}
+            e => panic!("Unexpected error: {}", e),
+        }
+
+        // top up with second foreign token
+        let foo_token = HumanAddr::from("foo_token");
+        let base = TopUp {
+            id: create.id.clone().unwrap(),
+        };
+        let top_up = HandleMsg::Receive(Cw20ReceiveMsg {
+            sender: HumanAddr::from("random"),
+            amount: Uint128(888),
+            msg: Some(to_binary(&base).unwrap()),
+        });
+        let info = mock_info(&foo_token, &[]);
+        let res = handle(&mut deps, init_env.clone(), info, top_up).unwrap();
+        assert_eq!(0, res.messages.len());
+        assert_eq!(attr("action", "top_up"), res.attributes[0]);
+
+        // withdraw it
+        let mut new_env = mock_env();
+        new_env.block.time = mock_time + mock_clawback_period + 1;
+        let id = create.id.clone().unwrap();
+        let info = mock_info(&create.holder, &[]);
+        let res = handle(
+            &mut deps,
+            new_env.clone(),
+            info,
+            HandleMsg::Withdraw {
+                id,
+                recipient: None,
+                msg: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(attr("action", "withdraw"), res.attributes[0]);
+        assert_eq!(3, res.messages.len());
+
+        // first message releases all native coins
+        assert_eq!(
+            res.messages[0],
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: create.holder.clone(),
+                amount: vec![coin(100, "fee"), coin(500, "stake"), coin(250, "random")],
+            })
+        );
+
+        // second one release bar cw20 token
+        let send_msg = Cw20HandleMsg::Transfer {
+            recipient: create.holder.clone(),
+            amount: Uint128(7890),
+        };
+        assert_eq!(
+            res.messages[1],
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: bar_token,
+                msg: to_binary(&send_msg).unwrap(),
+                send: vec![],
+            })
+        );
 
-        let res = init(&mut deps, init_env.clone(), info, init_msg).unwrap();
-        assert_eq!(0, res.messages.len());
+        // third one release foo cw20 token
+        let send_msg = Cw20HandleMsg::Transfer {
+            recipient: create.holder.clone(),
+            amount: Uint128(888),
+        };
+        assert_eq!(
+            res.messages[2],
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: foo_token,
+                msg: to_binary(&send_msg).unwrap(),
+                send: vec![],
+            })
+        );
+    }
 
-        // create a clawback
-        let mock_clawback_period = 1;
-        let create = CreateMsg {
-            id: "foobar".to_string(),
-            holder: HumanAddr::from("holder"),
-            backup: HumanAddr::from("backup"),
-            clawback_period: mock_clawback_period,
-            cw20_whitelist: Some(vec![HumanAddr::from("other-token")]),
+    #[test]
+    fn total_locked() {
+        let (create, mock_time, mock_clawback_period, mut deps, _) = basic_native_setup();
+        let env = mock_env();
+
+        // create a second clawback with native tokens
+        let second = CreateMsg {
+            id: Some("second".to_string()),
+            ..create.clone()
         };
-        let receive = Cw20ReceiveMsg {
-            sender: HumanAddr::from("source"),
-            amount: Uint128(100),
-            msg: Some(to_binary(&HandleMsg::Create(create.clone())).unwrap()),
+        let info = mock_info(HumanAddr::from("source"), &coins(50, "tokens"));
+        handle(&mut deps, env.clone(), info, HandleMsg::Create(second)).unwrap();
+
+        // and a third one with cw20 tokens
+        let third = CreateMsg {
+            id: Some("third".to_string()),
+            ..create.clone()
         };
-        let token_contract = HumanAddr::from("my-cw20-token");
-        let info = mock_info(&token_contract, &[]);
-        let msg = HandleMsg::Receive(receive.clone());
-        let res = handle(&mut deps, init_env, info, msg).unwrap();
-        assert_eq!(0, res.messages.len());
-        assert_eq!(attr("action", "create"), res.attributes[0]);
-        // ensure the whitelist is what we expect
-        let details = query_details(&deps, "foobar".to_string()).unwrap();
+        let receive = HandleMsg::Receive(Cw20ReceiveMsg {
+            sender: HumanAddr::from("source"),
+            amount: Uint128(777),
+            msg: Some(to_binary(&ReceiveMsg::Create(third)).unwrap()),
+        });
+        let info = mock_info(HumanAddr::from("my-cw20-token"), &[]);
+        handle(&mut deps, env, info, receive).unwrap();
 
+        let total = query_total_locked(&deps).unwrap();
         assert_eq!(
-            details,
-            DetailsResponse {
-                id: "foobar".to_string(),
-                holder: HumanAddr::from("holder"),
-                backup: HumanAddr::from("backup"),
-                end_time: mock_time + mock_clawback_period,
-                clawback_period: mock_clawback_period,
-                native_balance: vec![],
-                cw20_balance: vec![Cw20CoinHuman {
+            total,
+            TotalLockedResponse {
+                native: coins(150, "tokens"),
+                cw20: vec![Cw20CoinHuman {
                     address: HumanAddr::from("my-cw20-token"),
-                    amount: Uint128(100),
+                    amount: Uint128(777),
                 }],
-                cw20_whitelist: vec![
-                    HumanAddr::from("other-token"),
-                    HumanAddr::from("my-cw20-token")
-                ],
             }
         );
 
-        // withdraw it
-        let id = create.id.clone();
+        // withdrawing the first clawback releases its funds
         let info = mock_info(&create.holder, &[]);
         let mut new_env = mock_env();
         new_env.block.time = mock_time + mock_clawback_period + 1;
-        let res = handle(&mut deps, new_env.clone(), info, HandleMsg::Withdraw { id }).unwrap();
-        assert_eq!(1, res.messages.len());
-        assert_eq!(attr("action", "withdraw"), res.attributes[0]);
-        let send_msg = Cw20HandleMsg::Transfer {
-            recipient: create.holder.clone(),
-            amount: receive.amount,
+        let withdraw = HandleMsg::Withdraw {
+            id: create.id.unwrap(),
+            recipient: None,
+            msg: None,
         };
+        handle(&mut deps, new_env, info, withdraw).unwrap();
+        let total = query_total_locked(&deps).unwrap();
         assert_eq!(
-            res.messages[0],
-            CosmosMsg::Wasm(WasmMsg::Execute {
+            total,
+            TotalLockedResponse {
+                native: coins(50, "tokens"),
+                cw20: vec![Cw20CoinHuman {
+                    address: HumanAddr::from("my-cw20-token"),
+                    amount: Uint128(777),
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn withdraw_partial() {
+        let (create, mock_time, mock_clawback_period, mut deps, _) = basic_native_setup();
+        let recipient = HumanAddr::from("exchange");
+        let mut new_env = mock_env();
+        new_env.block.time = mock_time + mock_clawback_period + 1;
+
+        // not before the end time
+        let info = mock_info(&create.holder, &[]);
+        let msg = HandleMsg::WithdrawPartial {
+            id: create.id.clone().unwrap(),
+            amount: Balance::from(coins(30, "tokens")),
+            recipient: recipient.clone(),
+            msg: None,
+        };
+        let mut env = mock_env();
+        env.block.time = mock_time;
+        match handle(&mut deps, env, info.clone(), msg.clone()).unwrap_err() {
+            ContractError::NotExpired {} => {}
+            e => panic!("Expected NotExpired, got {}", e),
+        }
+
+        // only by the holder
+        let backup_info = mock_info(&create.backup, &[]);
+        match handle(&mut deps, new_env.clone(), backup_info, msg.clone()).unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            e => panic!("Expected Unauthorized, got {}", e),
+        }
+
+        // not more than in the clawback
+        let too_much = HandleMsg::WithdrawPartial {
+            id: create.id.clone().unwrap(),
+            amount: Balance::from(vec![coin(60, "tokens"), coin(60, "tokens")]),
+            recipient: recipient.clone(),
+            msg: None,
+        };
+        match handle(&mut deps, new_env.clone(), info.clone(), too_much).unwrap_err() {
+            ContractError::NotEnoughBalance {} => {}
+            e => panic!("Expected NotEnoughBalance, got {}", e),
+        }
+
+        // withdraw a part of it
+        let res = handle(&mut deps, new_env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(attr("action", "withdraw_partial"), res.attributes[0]);
+        assert_eq!(attr("to", &recipient), res.attributes[2]);
+        assert_eq!(
+            res.messages,
+            vec![CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: recipient.clone(),
+                amount: coins(30, "tokens"),
+            })]
+        );
+        let details = query_details(&deps, mock_env(), create.id.clone().unwrap()).unwrap();
+        assert_eq!(details.native_balance, coins(70, "tokens"));
+
+        // withdrawing the rest deletes the clawback
+        let msg = HandleMsg::WithdrawPartial {
+            id: create.id.clone().unwrap(),
+            amount: Balance::from(coins(70, "tokens")),
+            recipient,
+            msg: None,
+        };
+        handle(&mut deps, new_env, info, msg).unwrap();
+        match query_details(&deps, mock_env(), create.id.unwrap()).unwrap_err() {
+            StdError::NotFound { .. } => {}
+            e => panic!("Expected NotFound, got {}", e),
+        }
+    }
+
+    #[test]
+    fn withdraw_to_contract() {
+        let (create, mock_time, mock_clawback_period, mut deps, balance) = basic_native_setup();
+
+        // add some cw20 tokens in another clawback
+        let token_contract = HumanAddr::from("my-cw20-token");
+        let cw20_create = CreateMsg {
+            id: Some("cw20-foobar".to_string()),
+            ..create.clone()
+        };
+        let receive = HandleMsg::Receive(Cw20ReceiveMsg {
+            sender: HumanAddr::from("source"),
+            amount: Uint128(100),
+            msg: Some(to_binary(&ReceiveMsg::Create(cw20_create.clone())).unwrap()),
+        });
+        let info = mock_info(&token_contract, &[]);
+        handle(&mut deps, mock_env(), info, receive).unwrap();
+
+        let mut new_env = mock_env();
+        new_env.block.time = mock_time + mock_clawback_period + 1;
+        let dex = HumanAddr::from("dex");
+        let hook = to_binary(&"swap").unwrap();
+
+        // native tokens go directly to the recipient
+        let info = mock_info(&create.holder, &[]);
+        let msg = HandleMsg::Withdraw {
+            id: create.id.clone().unwrap(),
+            recipient: Some(dex.clone()),
+            msg: Some(hook.clone()),
+        };
+        let res = handle(&mut deps, new_env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(attr("to", &dex), res.attributes[2]);
+        assert_eq!(
+            res.messages,
+            vec![CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: dex.clone(),
+                amount: balance,
+            })]
+        );
+
+        // cw20 tokens are sent with the hook
+        let msg = HandleMsg::Withdraw {
+            id: cw20_create.id.unwrap(),
+            recipient: Some(dex.clone()),
+            msg: Some(hook.clone()),
+        };
+        let res = handle(&mut deps, new_env, info, msg).unwrap();
+        let send_msg = Cw20HandleMsg::Send {
+            contract: dex,
+            amount: Uint128(100),
+            msg: Some(hook),
+        };
+        assert_eq!(
+            res.messages,
+            vec![CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr: token_contract,
                 msg: to_binary(&send_msg).unwrap(),
                 send: vec![],
-            })
+            })]
         );
+    }
 
-        // second attempt fails (not found)
-        let id = create.id.clone();
+    #[test]
+    fn height_based_period() {
+        let (create, _, _, mut deps, _) = basic_native_setup();
+
+        // create a clawback that expires after 10 blocks
+        let env = mock_env();
+        let by_height = CreateMsg {
+            id: Some("by-height".to_string()),
+            clawback_period: Duration::Height(10),
+            ..create.clone()
+        };
+        let info = mock_info(HumanAddr::from("source"), &coins(100, "tokens"));
+        handle(
+            &mut deps,
+            env.clone(),
+            info,
+            HandleMsg::Create(by_height.clone()),
+        )
+        .unwrap();
+        let details = query_details(&deps, mock_env(), by_height.id.clone().unwrap()).unwrap();
+        assert_eq!(details.clawback_period, Duration::Height(10));
+        assert_eq!(
+            details.end_time,
+            Expiration::AtHeight(env.block.height + 10)
+        );
+
+        // not expired at the end height, even if a lot of time passed
+        let mut new_env = mock_env();
+        new_env.block.height = env.block.height + 10;
+        new_env.block.time = env.block.time + 1_000_000;
         let info = mock_info(&create.holder, &[]);
-        let res = handle(&mut deps, new_env, info, HandleMsg::Withdraw { id });
-        match res.unwrap_err() {
-            ContractError::Std(StdError::NotFound { .. }) => {}
-            e => panic!("Expected NotFound, got {}", e),
+        let withdraw = HandleMsg::Withdraw {
+            id: by_height.id.clone().unwrap(),
+            recipient: None,
+            msg: None,
+        };
+        match handle(&mut deps, new_env.clone(), info.clone(), withdraw.clone()).unwrap_err() {
+            ContractError::NotExpired {} => {}
+            e => panic!("Expected NotExpired, got {}", e),
+        }
+
+        // refresh counts the blocks from the current height
+        let backup = mock_info(&create.backup, &[]);
+        let refresh = HandleMsg::Refresh {
+            id: by_height.id.clone().unwrap(),
+        };
+        handle(&mut deps, new_env.clone(), backup.clone(), refresh).unwrap();
+        let details = query_details(&deps, mock_env(), by_height.id.clone().unwrap()).unwrap();
+        assert_eq!(
+            details.end_time,
+            Expiration::AtHeight(env.block.height + 20)
+        );
+
+        // time and height periods are not compatible for transfers
+        let transfer = HandleMsg::ClawbackTransfer {
+            from_id: create.id.clone().unwrap(),
+            to_id: by_height.id.clone().unwrap(),
+            amount: Balance::from(coins(1, "tokens")),
+            create: None,
+        };
+        match handle(&mut deps, env.clone(), backup, transfer).unwrap_err() {
+            ContractError::ContractMismatch {} => {}
+            e => panic!("Expected ContractMismatch, got {}", e),
         }
+
+        // expired once the end height passes
+        new_env.block.height = env.block.height + 21;
+        handle(&mut deps, new_env, info, withdraw).unwrap();
     }
 
     #[test]
-    fn remove_tokens_proper() {
-        let mut tokens = GenericBalance::default();
-        tokens.add_tokens(Balance::from(vec![coin(123, "atom"), coin(789, "eth")]));
+    fn migrate_from_v0_3() {
+        let mut deps = mock_dependencies(&[]);
+
+        // a store as left by 0.3.x: only the contract version and the clawbacks
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "0.3.1").unwrap();
+        let token = deps
+            .api
+            .canonical_address(&HumanAddr::from("my-cw20-token"))
+            .unwrap();
+        for (id, amount) in [("first", 100), ("second", 50)].iter() {
+            let old = ClawbackV0_3 {
+                backup: deps
+                    .api
+                    .canonical_address(&HumanAddr::from("backup"))
+                    .unwrap(),
+                holder: deps
+                    .api
+                    .canonical_address(&HumanAddr::from("holder"))
+                    .unwrap(),
+                end_time: 1000,
+                clawback_period: 100,
+                balance: GenericBalance {
+                    native: coins(*amount, "tokens"),
+                    cw20: vec![Cw20Coin {
+                        address: token.clone(),
+                        amount: Uint128(7),
+                    }],
+                },
+                cw20_whitelist: vec![token.clone()],
+            };
+            bucket(&mut deps.storage, PREFIX_CLAWBACK)
+                .save(id.as_bytes(), &old)
+                .unwrap();
+        }
+
+        // one clawback per migration (a limit of 0 still converts one)
+        let info = mock_info(HumanAddr::from("anyone"), &[]);
+        let msg = MigrateMsg {
+            native_sink: Some(HumanAddr::from("community_pool")),
+            admin: None,
+            limit: Some(0),
+        };
+        let res = migrate(&mut deps, mock_env(), info.clone(), msg).unwrap();
+        assert_eq!(attr("action", "migrate"), res.attributes[0]);
+        assert_eq!(attr("from_version", "0.3.1"), res.attributes[1]);
+        assert_eq!(attr("to_version", CONTRACT_VERSION), res.attributes[2]);
+        assert_eq!(attr("pending", "true"), res.attributes[3]);
+        let version = get_contract_version(&deps.storage).unwrap();
+        assert_eq!(version.version, CONTRACT_VERSION);
+
+        // the contract waits for the rest (not all clawbacks are counted in yet)
+        let burn = HandleMsg::Burn {
+            id: "first".to_string(),
+        };
+        let err = super::handle(&mut deps, mock_env(), info.clone(), burn).unwrap_err();
+        match err {
+            ContractError::MigrationPending {} => {}
+            e => panic!("Expected MigrationPending, got {}", e),
+        }
+        let msg = MigrateMsg {
+            native_sink: None,
+            admin: None,
+            limit: Some(1),
+        };
+        let res = migrate(&mut deps, mock_env(), info, msg).unwrap();
+        assert_eq!(attr("pending", "false"), res.attributes[3]);
+
+        // clawbacks are in the new layout
+        let details = query_details(&deps, mock_env(), "first".to_string()).unwrap();
+        assert_eq!(details.end_time, Expiration::AtTime(1000));
+        assert_eq!(details.clawback_period, Duration::Time(100));
+        assert_eq!(details.native_balance, coins(100, "tokens"));
+
+        // the indexes are built
+        let by_holder = QueryMsg::ByHolder {
+            holder: HumanAddr::from("holder"),
+            start_after: None,
+            limit: None,
+        };
+        let res: ListResponse = from_binary(&query(&deps, mock_env(), by_holder).unwrap()).unwrap();
+        assert_eq!(res.clawbacks, vec!["first", "second"]);
+        let expired = QueryMsg::Expired {
+            start_after: None,
+            limit: None,
+        };
+        let res: ExpiringResponse =
+            from_binary(&query(&deps, mock_env(), expired).unwrap()).unwrap();
+        assert_eq!(res.clawbacks.len(), 2);
+        assert_eq!(res.clawbacks[0].end_time, Expiration::AtTime(1000));
+
+        // the total locked balance is computed
+        let total = query_total_locked(&deps).unwrap();
         assert_eq!(
-            tokens.remove_tokens(Balance::from(vec![coin(456, "atom"), coin(12, "btc")])),
-            Err(())
+            total,
+            TotalLockedResponse {
+                native: coins(150, "tokens"),
+                cw20: vec![Cw20CoinHuman {
+                    address: HumanAddr::from("my-cw20-token"),
+                    amount: Uint128(14),
+                }],
+            }
         );
-        assert_eq!(tokens.native, vec![coin(123, "atom"), coin(789, "eth")]);
+
+        // and the clawbacks can be burned to the new sink
+        let mut env = mock_env();
+        env.block.time = 1000;
+        let info = mock_info(HumanAddr::from("backup"), &[]);
+        let burn = HandleMsg::Burn {
+            id: "second".to_string(),
+        };
+        let res = handle(&mut deps, env, info, burn).unwrap();
         assert_eq!(
-            tokens.remove_tokens(Balance::from(vec![coin(1, "atom"), coin(1, "btc")])),
-            Err(())
+            res.messages[0],
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("community_pool"),
+                amount: coins(50, "tokens"),
+            })
         );
-        assert_eq!(tokens.native, vec![coin(123, "atom"), coin(789, "eth")]);
+
+        // migrating to the same version again does not change anything
+        let info = mock_info(HumanAddr::from("anyone"), &[]);
+        migrate(
+            &mut deps,
+            mock_env(),
+            info,
+            MigrateMsg {
+                native_sink: None,
+                admin: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+        let details = query_details(&deps, mock_env(), "first".to_string()).unwrap();
+        assert_eq!(details.end_time, Expiration::AtTime(1000));
         assert_eq!(
-            tokens.remove_tokens(Balance::from(vec![coin(1, "atom"), coin(1, "eth")])),
-            Ok(())
+            query_total_locked(&deps).unwrap().native,
+            coins(100, "tokens")
         );
-        assert_eq!(tokens.native, vec![coin(122, "atom"), coin(788, "eth")]);
     }
 
-    #[test]
-    fn remove_cw_tokens_proper() {
-        let mut tokens = GenericBalance::default();
-        let bar_token = CanonicalAddr(b"bar_token".to_vec().into());
-        let foo_token = CanonicalAddr(b"foo_token".to_vec().into());
-        tokens.add_tokens(Balance::Cw20(Cw20Coin {
-            address: foo_token.clone(),
-            amount: Uint128(12345),
-        }));
-        assert_eq!(
-            tokens.remove_tokens(Balance::Cw20(Cw20Coin {
-                address: bar_token.clone(),
-                amount: Uint128(777),
-            })),
-            Err(())
+    #[test]
+    fn migrate_checks_version() {
+        let mut deps = mock_dependencies(&[]);
+        let info = mock_info(HumanAddr::from("anyone"), &[]);
+
+        set_contract_version(&mut deps.storage, "crates.io:cw20-escrow", "0.3.1").unwrap();
+        let res = migrate(
+            &mut deps,
+            mock_env(),
+            info.clone(),
+            MigrateMsg {
+                native_sink: None,
+                admin: None,
+                limit: None,
+            },
+        );
+        match res.unwrap_err() {
+            ContractError::CannotMigrate { previous_contract } => {
+                assert_eq!(previous_contract, "crates.io:cw20-escrow")
+            }
+            e => panic!("Expected CannotMigrate, got {}", e),
+        }
+
+        for version in ["99.0.0", "v0.3", "0.3"].iter() {
+            set_contract_version(&mut deps.storage, CONTRACT_NAME, *version).unwrap();
+            let res = migrate(
+                &mut deps,
+                mock_env(),
+                info.clone(),
+                MigrateMsg {
+                    native_sink: None,
+                    admin: None,
+                    limit: None,
+                },
+            );
+            match res.unwrap_err() {
+                ContractError::CannotMigrateVersion { previous_version } => {
+                    assert_eq!(previous_version, *version)
+                }
+                e => panic!("Expected CannotMigrateVersion, got {}", e),
+            }
+        }
+    }
+
+    #[test]
+    fn list_paginated() {
+        let (create, _, _, mut deps, balance) = basic_native_setup();
+        for id in ["alpha", "omega", "lambda"].iter() {
+            let msg = CreateMsg {
+                id: Some(id.to_string()),
+                ..create.clone()
+            };
+            let info = mock_info(HumanAddr::from("source"), &balance);
+            handle(&mut deps, mock_env(), info, HandleMsg::Create(msg)).unwrap();
+        }
+
+        // all ids by default
+        let list = QueryMsg::List {
+            start_after: None,
+            limit: None,
+        };
+        let res: ListResponse = from_binary(&query(&deps, mock_env(), list).unwrap()).unwrap();
+        assert_eq!(res.clawbacks, vec!["alpha", "foobar", "lambda", "omega"]);
+
+        // page through them
+        let list = QueryMsg::List {
+            start_after: Some("foobar".to_string()),
+            limit: Some(1),
+        };
+        let res: ListResponse = from_binary(&query(&deps, mock_env(), list).unwrap()).unwrap();
+        assert_eq!(res.clawbacks, vec!["lambda"]);
+
+        // or get the details
+        let list = QueryMsg::ListDetails {
+            start_after: Some("alpha".to_string()),
+            limit: Some(2),
+        };
+        let res: ListDetailsResponse =
+            from_binary(&query(&deps, mock_env(), list).unwrap()).unwrap();
+        assert_eq!(
+            res.clawbacks,
+            vec![
+                query_details(&deps, mock_env(), "foobar".to_string()).unwrap(),
+                query_details(&deps, mock_env(), "lambda".to_string()).unwrap(),
+            ]
+        );
+
+        // the limit is capped
+        for i in 0..40 {
+            let msg = CreateMsg {
+                id: Some(format!("many{:02}", i)),
+                ..create.clone()
+            };
+            let info = mock_info(HumanAddr::from("source"), &balance);
+            handle(&mut deps, mock_env(), info, HandleMsg::Create(msg)).unwrap();
+        }
+        let list = QueryMsg::List {
+            start_after: None,
+            limit: Some(100),
+        };
+        let res: ListResponse = from_binary(&query(&deps, mock_env(), list).unwrap()).unwrap();
+        assert_eq!(res.clawbacks.len(), MAX_LIMIT as usize);
+    }
+
+    #[test]
+    fn list_by_holder_and_backup() {
+        let (create, mock_time, mock_clawback_period, mut deps, balance) = basic_native_setup();
+        for (id, holder, backup) in [
+            ("alpha", "holder", "backup2"),
+            ("beta", "holder2", "backup"),
+            ("gamma", "holder", "backup"),
+        ]
+        .iter()
+        {
+            let msg = CreateMsg {
+                id: Some(id.to_string()),
+                holder: HumanAddr::from(*holder),
+                backup: HumanAddr::from(*backup),
+                ..create.clone()
+            };
+            let info = mock_info(HumanAddr::from("source"), &balance);
+            handle(&mut deps, mock_env(), info, HandleMsg::Create(msg)).unwrap();
+        }
+
+        let by_holder = |deps: &Extern<_, _, _>, holder: &str, start_after: Option<&str>| {
+            let msg = QueryMsg::ByHolder {
+                holder: HumanAddr::from(holder),
+                start_after: start_after.map(String::from),
+                limit: Some(2),
+            };
+            let res: ListResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            res.clawbacks
+        };
+        assert_eq!(by_holder(&deps, "holder", None), vec!["alpha", "foobar"]);
+        assert_eq!(by_holder(&deps, "holder", Some("foobar")), vec!["gamma"]);
+        assert_eq!(by_holder(&deps, "holder2", None), vec!["beta"]);
+        assert_eq!(by_holder(&deps, "backup", None), Vec::<String>::new());
+
+        let msg = QueryMsg::ByBackup {
+            backup: HumanAddr::from("backup"),
+            start_after: None,
+            limit: None,
+        };
+        let res: ListResponse = from_binary(&query(&deps, mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(res.clawbacks, vec!["beta", "foobar", "gamma"]);
+
+        // withdrawn clawbacks are gone from the index
+        let mut new_env = mock_env();
+        new_env.block.time = mock_time + mock_clawback_period + 1;
+        let info = mock_info(&create.holder, &[]);
+        let withdraw = HandleMsg::Withdraw {
+            id: "alpha".to_string(),
+            recipient: None,
+            msg: None,
+        };
+        handle(&mut deps, new_env, info, withdraw).unwrap();
+        assert_eq!(by_holder(&deps, "holder", None), vec!["foobar", "gamma"]);
+    }
+
+    #[test]
+    fn expiring_and_expired() {
+        let (create, mock_time, mock_clawback_period, mut deps, balance) = basic_native_setup();
+        let mut env = mock_env();
+        env.block.time = mock_time;
+        for (id, clawback_period) in
+            [("long", Duration::Time(100)), ("tall", Duration::Height(5))].iter()
+        {
+            let msg = CreateMsg {
+                id: Some(id.to_string()),
+                clawback_period: *clawback_period,
+                ..create.clone()
+            };
+            let info = mock_info(HumanAddr::from("source"), &balance);
+            handle(&mut deps, env.clone(), info, HandleMsg::Create(msg)).unwrap();
+        }
+
+        let expiring = |deps: &Extern<_, _, _>,
+                        start: Expiration,
+                        end: Expiration,
+                        start_after: Option<ExpiringClawback>,
+                        limit: Option<u32>| {
+            let msg = QueryMsg::ExpiringBetween {
+                start,
+                end,
+                start_after,
+                limit,
+            };
+            let res: ExpiringResponse =
+                from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            res.clawbacks
+        };
+        let foobar = ExpiringClawback {
+            id: "foobar".to_string(),
+            end_time: Expiration::AtTime(mock_time + mock_clawback_period),
+        };
+        let long = ExpiringClawback {
+            id: "long".to_string(),
+            end_time: Expiration::AtTime(mock_time + 100),
+        };
+        let tall = ExpiringClawback {
+            id: "tall".to_string(),
+            end_time: Expiration::AtHeight(env.block.height + 5),
+        };
+
+        // both ends are inclusive
+        let res = expiring(
+            &deps,
+            Expiration::AtTime(mock_time + mock_clawback_period),
+            Expiration::AtTime(mock_time + 100),
+            None,
+            None,
         );
-        assert_eq!(
-            tokens.cw20,
-            vec![Cw20Coin {
-                address: foo_token.clone(),
-                amount: Uint128(12345),
-            }]
+        assert_eq!(res, vec![foobar.clone(), long.clone()]);
+        let res = expiring(
+            &deps,
+            Expiration::AtTime(0),
+            Expiration::AtTime(mock_time + 99),
+            None,
+            None,
         );
-        assert_eq!(
-            tokens.remove_tokens(Balance::Cw20(Cw20Coin {
-                address: foo_token.clone(),
-                amount: Uint128(23400),
-            })),
-            Err(())
+        assert_eq!(res, vec![foobar.clone()]);
+        let res = expiring(
+            &deps,
+            Expiration::AtTime(0),
+            Expiration::AtTime(u64::MAX),
+            Some(foobar.clone()),
+            Some(1),
+        );
+        assert_eq!(res, vec![long.clone()]);
+        let res = expiring(
+            &deps,
+            Expiration::AtHeight(0),
+            Expiration::AtHeight(u64::MAX),
+            None,
+            None,
         );
+        assert_eq!(res, vec![tall.clone()]);
+
+        // time and height cannot be mixed
+        let msg = QueryMsg::ExpiringBetween {
+            start: Expiration::AtHeight(0),
+            end: Expiration::AtTime(u64::MAX),
+            start_after: None,
+            limit: None,
+        };
+        query(&deps, mock_env(), msg).unwrap_err();
+
+        let expired = |deps: &Extern<_, _, _>,
+                       env: Env,
+                       start_after: Option<ExpiringClawback>,
+                       limit: Option<u32>| {
+            let msg = QueryMsg::Expired { start_after, limit };
+            let res: ExpiringResponse = from_binary(&query(deps, env, msg).unwrap()).unwrap();
+            res.clawbacks
+        };
+        assert_eq!(expired(&deps, env.clone(), None, None), vec![]);
+
+        // not yet expired at the end time / height itself
+        let mut new_env = env.clone();
+        new_env.block.time = mock_time + mock_clawback_period;
+        new_env.block.height += 5;
+        assert_eq!(expired(&deps, new_env.clone(), None, None), vec![]);
+
+        // height based ones are listed first
+        new_env.block.time += 1;
+        new_env.block.height += 1;
         assert_eq!(
-            tokens.cw20,
-            vec![Cw20Coin {
-                address: foo_token.clone(),
-                amount: Uint128(12345),
-            }]
+            expired(&deps, new_env.clone(), None, None),
+            vec![tall.clone(), foobar.clone()]
         );
         assert_eq!(
-            tokens.remove_tokens(Balance::Cw20(Cw20Coin {
-                address: foo_token.clone(),
-                amount: Uint128(1),
-            })),
-            Ok(())
+            expired(&deps, new_env.clone(), None, Some(1)),
+            vec![tall.clone()]
         );
         assert_eq!(
-            tokens.cw20,
-            vec![Cw20Coin {
-                address: foo_token.clone(),
-                amount: Uint128(12344),
-            }]
+            expired(&deps, new_env.clone(), Some(tall), None),
+            vec![foobar]
         );
-    }
 
-    #[test]
-    fn add_tokens_proper() {
-        let mut tokens = GenericBalance::default();
-        tokens.add_tokens(Balance::from(vec![coin(123, "atom"), coin(789, "eth")]));
-        tokens.add_tokens(Balance::from(vec![coin(456, "atom"), coin(12, "btc")]));
-        assert_eq!(
-            tokens.native,
-            vec![coin(579, "atom"), coin(789, "eth"), coin(12, "btc")]
+        // refreshing moves the clawback in the index
+        let info = mock_info(&create.holder, &[]);
+        let msg = HandleMsg::Refresh {
+            id: "foobar".to_string(),
+        };
+        handle(&mut deps, new_env.clone(), info, msg).unwrap();
+        let res = expired(&deps, new_env.clone(), None, None);
+        assert_eq!(res.len(), 1);
+        let res = expiring(
+            &deps,
+            Expiration::AtTime(0),
+            Expiration::AtTime(u64::MAX),
+            None,
+            None,
         );
+        let refreshed = ExpiringClawback {
+            id: "foobar".to_string(),
+            end_time: Expiration::AtTime(new_env.block.time + mock_clawback_period),
+        };
+        assert_eq!(res, vec![refreshed, long]);
     }
 
     #[test]
-    fn add_cw_tokens_proper() {
-        let mut tokens = GenericBalance::default();
-        let bar_token = CanonicalAddr(b"bar_token".to_vec().into());
-        let foo_token = CanonicalAddr(b"foo_token".to_vec().into());
-        tokens.add_tokens(Balance::Cw20(Cw20Coin {
-            address: foo_token.clone(),
-            amount: Uint128(12345),
-        }));
-        tokens.add_tokens(Balance::Cw20(Cw20Coin {
-            address: bar_token.clone(),
-            amount: Uint128(777),
-        }));
-        tokens.add_tokens(Balance::Cw20(Cw20Coin {
-            address: foo_token.clone(),
-            amount: Uint128(23400),
-        }));
-        assert_eq!(
-            tokens.cw20,
-            vec![
-                Cw20Coin {
-                    address: foo_token,
-                    amount: Uint128(35745),
-                },
-                Cw20Coin {
-                    address: bar_token,
-                    amount: Uint128(777),
-                }
-            ]
-        );
+    fn create_checks_id() {
+        let (create, _, _, mut deps, balance) = basic_native_setup();
+        let long = "a".repeat(20);
+        let too_long = "a".repeat(21);
+        for id in [
+            "",
+            "ab",
+            too_long.as_str(),
+            "foo bar",
+            "foo/bar",
+            "fööö",
+            "foo\0",
+        ]
+        .iter()
+        {
+            let msg = CreateMsg {
+                id: Some(id.to_string()),
+                ..create.clone()
+            };
+            let info = mock_info(HumanAddr::from("source"), &balance);
+            let err = handle(&mut deps, mock_env(), info, HandleMsg::Create(msg)).unwrap_err();
+            match err {
+                ContractError::InvalidId {} => {}
+                e => panic!("unexpected error for {:?}: {:?}", id, e),
+            }
+        }
+        for id in ["abc", long.as_str(), "Foo_Bar-9"].iter() {
+            let msg = CreateMsg {
+                id: Some(id.to_string()),
+                ..create.clone()
+            };
+            let info = mock_info(HumanAddr::from("source"), &balance);
+            handle(&mut deps, mock_env(), info, HandleMsg::Create(msg)).unwrap();
+        }
+
+        // also checked when creating with cw20 tokens
+        let msg = CreateMsg {
+            id: Some("ab".to_string()),
+            ..create
+        };
+        let receive = Cw20ReceiveMsg {
+            sender: HumanAddr::from("source"),
+            amount: Uint128(100),
+            msg: Some(to_binary(&ReceiveMsg::Create(msg)).unwrap()),
+        };
+        let info = mock_info(HumanAddr::from("my-cw20-token"), &[]);
+        let err = handle(&mut deps, mock_env(), info, HandleMsg::Receive(receive)).unwrap_err();
+        match err {
+            ContractError::InvalidId {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
     }
 
     #[test]
-    fn transfer_cw20() {
-        let mut deps = mock_dependencies(&[]);
+    fn create_with_assigned_id() {
+        let (create, _, _, mut deps, balance) = basic_native_setup();
 
-        // init an empty contract
-        let init_msg = InitMsg {};
-        let mock_clawback_period = 2;
-        let mock_time = 1571920875;
-        let mut init_env = mock_env();
-        init_env.block.time = mock_time;
-        let token_contract = HumanAddr::from("my-cw20-token");
+        // a user picked the second id before
+        let msg = CreateMsg {
+            id: Some("clawback-2".to_string()),
+            ..create.clone()
+        };
+        let info = mock_info(HumanAddr::from("source"), &balance);
+        let res = handle(&mut deps, mock_env(), info, HandleMsg::Create(msg)).unwrap();
+        let data: CreateResponse = from_binary(&res.data.unwrap()).unwrap();
+        assert_eq!(data.id, "clawback-2");
 
-        let info = mock_info(&HumanAddr::from("anyone"), &[]);
-        let res = init(&mut deps, init_env.clone(), info, init_msg).unwrap();
-        assert_eq!(0, res.messages.len());
-        // create two clawbacks
-        for idc in ["foo", "bar", "wrong-per", "wrong-back"].iter() {
-            let create = CreateMsg {
-                id: idc.to_string(),
-                backup: if *idc == "wrong-back" {
-                    HumanAddr::from("backup2")
-                } else {
-                    HumanAddr::from("backup")
-                },
-                holder: HumanAddr::from(*idc),
-                clawback_period: if *idc == "wrong-per" {
-                    mock_clawback_period - 1
-                } else {
-                    mock_clawback_period
-                },
-                cw20_whitelist: Some(vec![HumanAddr::from("other-token")]),
+        let mut assigned = vec![];
+        for _ in 0..2 {
+            let msg = CreateMsg {
+                id: None,
+                ..create.clone()
             };
-            let receive = Cw20ReceiveMsg {
-                sender: HumanAddr::from("source"),
-                amount: Uint128(100),
-                msg: Some(to_binary(&HandleMsg::Create(create.clone())).unwrap()),
+            let info = mock_info(HumanAddr::from("source"), &balance);
+            let res = handle(&mut deps, mock_env(), info, HandleMsg::Create(msg)).unwrap();
+            let data: CreateResponse = from_binary(&res.data.unwrap()).unwrap();
+            assert_eq!(attr("id", &data.id), res.attributes[1]);
+            assigned.push(data.id);
+        }
+        assert_eq!(assigned, vec!["clawback-1", "clawback-3"]);
+        let details = query_details(&deps, mock_env(), "clawback-3".to_string()).unwrap();
+        assert_eq!(details.holder, create.holder);
+    }
+
+    #[test]
+    fn multisig_backup() {
+        let (create, mock_time, _, mut deps, balance) = basic_native_setup();
+        let mut env = mock_env();
+        env.block.time = mock_time;
+        let signers: Vec<HumanAddr> = ["signer1", "signer2", "signer3"]
+            .iter()
+            .map(|s| HumanAddr::from(*s))
+            .collect();
+        let multisig = Multisig {
+            signers: signers.clone(),
+            threshold: 2,
+        };
+
+        // threshold must be reachable by distinct signers
+        for (signers, threshold) in [
+            (signers.clone(), 0),
+            (signers.clone(), 4),
+            (vec![signers[0].clone(), signers[0].clone()], 2),
+        ]
+        .iter()
+        {
+            let msg = CreateMsg {
+                id: Some("cold".to_string()),
+                backup_multisig: Some(Multisig {
+                    signers: signers.clone(),
+                    threshold: *threshold,
+                }),
+                ..create.clone()
             };
+            let info = mock_info(HumanAddr::from("source"), &balance);
+            let err = handle(&mut deps, env.clone(), info, HandleMsg::Create(msg)).unwrap_err();
+            match err {
+                ContractError::InvalidThreshold {} => {}
+                e => panic!("unexpected error: {:?}", e),
+            }
+        }
 
-            let info = mock_info(&token_contract, &vec![]);
-            let msg = HandleMsg::Receive(receive.clone());
+        for id in ["cold", "cold2", "cold3"].iter() {
+            let msg = CreateMsg {
+                id: Some(id.to_string()),
+                clawback_period: Duration::Time(100),
+                backup_multisig: Some(multisig.clone()),
+                ..create.clone()
+            };
+            let info = mock_info(HumanAddr::from("source"), &balance);
+            handle(&mut deps, env.clone(), info, HandleMsg::Create(msg)).unwrap();
+        }
+        // signers are stored in canonical order
+        let details = query_details(&deps, mock_env(), "cold".to_string()).unwrap();
+        let mut stored = details.backup_multisig.unwrap();
+        stored.signers.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        assert_eq!(stored, multisig);
 
-            let res = handle(&mut deps, init_env.clone(), info, msg).unwrap();
-            assert_eq!(0, res.messages.len());
-            assert_eq!(attr("action", "create"), res.attributes[0]);
+        // the backup key cannot act alone
+        let info = mock_info(&create.backup, &[]);
+        for msg in [
+            HandleMsg::Burn {
+                id: "cold".to_string(),
+            },
+            HandleMsg::Refresh {
+                id: "cold".to_string(),
+            },
+            HandleMsg::ClawbackTransfer {
+                from_id: "cold".to_string(),
+                to_id: "cold2".to_string(),
+                amount: Balance::from(balance.clone()),
+                create: None,
+            },
+            HandleMsg::UpdateBackup {
+                id: "cold".to_string(),
+                backup: HumanAddr::from("new-backup"),
+            },
+        ]
+        .iter()
+        {
+            let err = handle(&mut deps, env.clone(), info.clone(), msg.clone()).unwrap_err();
+            match err {
+                ContractError::Unauthorized {} => {}
+                e => panic!("unexpected error: {:?}", e),
+            }
+        }
 
-            // ensure the details is what we expect
-            let details = query_details(&deps, idc.to_string()).unwrap();
-            assert_eq!(
-                details,
-                DetailsResponse {
-                    id: idc.to_string(),
-                    backup: if *idc == "wrong-back" {
-                        HumanAddr::from("backup2")
-                    } else {
-                        HumanAddr::from("backup")
-                    },
-                    holder: HumanAddr::from(*idc),
-                    clawback_period: if *idc == "wrong-per" {
-                        mock_clawback_period - 1
-                    } else {
-                        mock_clawback_period
-                    },
-                    end_time: if *idc == "wrong-per" {
-                        mock_time + mock_clawback_period - 1
-                    } else {
-                        mock_time + mock_clawback_period
-                    },
-                    native_balance: vec![],
-                    cw20_balance: vec![Cw20CoinHuman {
-                        address: HumanAddr::from("my-cw20-token"),
-                        amount: Uint128(100),
-                    }],
-                    cw20_whitelist: vec![
-                        HumanAddr::from("other-token"),
-                        HumanAddr::from("my-cw20-token")
-                    ],
-                }
-            );
+        // only signers propose, and only on multisig clawbacks
+        let propose = |id: &str, action: BackupAction| HandleMsg::Propose {
+            id: id.to_string(),
+            action,
+        };
+        let info = mock_info(&create.backup, &[]);
+        let err = handle(
+            &mut deps,
+            env.clone(),
+            info,
+            propose("cold", BackupAction::Burn {}),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        let info = mock_info(&signers[0], &[]);
+        let err = handle(
+            &mut deps,
+            env.clone(),
+            info,
+            propose("foobar", BackupAction::Burn {}),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::NoMultisig {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        // a transfer from cold to cold2 needs two votes
+        let action = BackupAction::Transfer {
+            to_id: "cold2".to_string(),
+            amount: Balance::from(coins(40, "tokens")),
+            create: None,
+        };
+        let info = mock_info(&signers[0], &[]);
+        let res = handle(
+            &mut deps,
+            env.clone(),
+            info.clone(),
+            propose("cold", action),
+        )
+        .unwrap();
+        assert_eq!(attr("proposal_id", 1), res.attributes[2]);
+        let proposal = query_proposal(&deps, 1).unwrap();
+        assert_eq!(proposal.votes, vec![signers[0].clone()]);
+        assert_eq!(proposal.expires, Expiration::AtTime(mock_time + 100));
+
+        let err = handle(
+            &mut deps,
+            env.clone(),
+            info,
+            HandleMsg::Vote { proposal_id: 1 },
+        )
+        .unwrap_err();
+        match err {
+            ContractError::AlreadyVoted {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        let info = mock_info(&create.backup, &[]);
+        let err = handle(
+            &mut deps,
+            env.clone(),
+            info,
+            HandleMsg::Vote { proposal_id: 1 },
+        )
+        .unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        let info = mock_info(&signers[2], &[]);
+        let res = handle(
+            &mut deps,
+            env.clone(),
+            info,
+            HandleMsg::Vote { proposal_id: 1 },
+        )
+        .unwrap();
+        assert_eq!(attr("executed", "true"), res.attributes[4]);
+        assert_eq!(attr("action", "transfer"), res.attributes[5]);
+        let details = query_details(&deps, mock_env(), "cold".to_string()).unwrap();
+        assert_eq!(details.native_balance, coins(60, "tokens"));
+        let details = query_details(&deps, mock_env(), "cold2".to_string()).unwrap();
+        assert_eq!(details.native_balance, coins(140, "tokens"));
+        // it passed, so it is gone
+        query_proposal(&deps, 1).unwrap_err();
+
+        // a burn that does not pass before the period ends
+        let info = mock_info(&signers[1], &[]);
+        handle(
+            &mut deps,
+            env.clone(),
+            info,
+            propose("cold", BackupAction::Burn {}),
+        )
+        .unwrap();
+        let res = query_list_proposals(&deps, None, None).unwrap();
+        assert_eq!(res.proposals.len(), 1);
+        assert_eq!(res.proposals[0].id, 2);
+        assert_eq!(res.proposals[0].action, BackupAction::Burn {});
+
+        let mut late_env = env.clone();
+        late_env.block.time = mock_time + 101;
+        let info = mock_info(&signers[0], &[]);
+        let err = handle(
+            &mut deps,
+            late_env,
+            info.clone(),
+            HandleMsg::Vote { proposal_id: 2 },
+        )
+        .unwrap_err();
+        match err {
+            ContractError::ProposalExpired {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        // in time, it burns the tokens
+        let res = handle(
+            &mut deps,
+            env.clone(),
+            info,
+            HandleMsg::Vote { proposal_id: 2 },
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: HumanAddr::from("community_pool"),
+                amount: coins(60, "tokens"),
+            })]
+        );
+        query_details(&deps, mock_env(), "cold".to_string()).unwrap_err();
+
+        // the signers may also hand the backup role over
+        let action = BackupAction::UpdateBackup {
+            backup: HumanAddr::from("new-backup"),
+        };
+        let info = mock_info(&signers[0], &[]);
+        handle(&mut deps, env.clone(), info, propose("cold2", action)).unwrap();
+        let info = mock_info(&signers[1], &[]);
+        let res = handle(
+            &mut deps,
+            env.clone(),
+            info,
+            HandleMsg::Vote { proposal_id: 3 },
+        )
+        .unwrap();
+        assert_eq!(attr("action", "update_backup"), res.attributes[5]);
+        let details = query_details(&deps, mock_env(), "cold2".to_string()).unwrap();
+        assert_eq!(details.pending_backup, Some(HumanAddr::from("new-backup")));
+        let info = mock_info(HumanAddr::from("new-backup"), &[]);
+        let msg = HandleMsg::AcceptBackup {
+            id: "cold2".to_string(),
+        };
+        handle(&mut deps, env.clone(), info, msg).unwrap();
+        let details = query_details(&deps, mock_env(), "cold2".to_string()).unwrap();
+        assert_eq!(details.backup, HumanAddr::from("new-backup"));
+        assert_eq!(details.backup_multisig, None);
+
+        // the new backup acts alone and the signers have no say anymore
+        let info = mock_info(&signers[0], &[]);
+        let err = handle(
+            &mut deps,
+            env.clone(),
+            info,
+            propose("cold2", BackupAction::Burn {}),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::NoMultisig {} => {}
+            e => panic!("unexpected error: {:?}", e),
         }
+        let info = mock_info(HumanAddr::from("new-backup"), &[]);
+        let burn = HandleMsg::Burn {
+            id: "cold2".to_string(),
+        };
+        handle(&mut deps, env.clone(), info, burn).unwrap();
+        query_details(&deps, mock_env(), "cold2".to_string()).unwrap_err();
 
-        // transfer it
-        let from_id = "foo".to_string();
-        let to_id = "bar".to_string();
-        let info = mock_info(HumanAddr::from(from_id.clone()), &[]);
-        let mut new_env = mock_env();
-        new_env.block.time = mock_time + 1;
-        let token_address = deps
-            .api
-            .canonical_address(&HumanAddr::from("my-cw20-token"))
-            .unwrap();
-        let res = handle(
+        // a new proposal replaces the open one of the same signer
+        let info = mock_info(&signers[0], &[]);
+        handle(
             &mut deps,
-            new_env.clone(),
+            env.clone(),
+            info.clone(),
+            propose("cold3", BackupAction::Refresh {}),
+        )
+        .unwrap();
+        handle(
+            &mut deps,
+            env.clone(),
             info,
-            HandleMsg::ClawbackTransfer {
-                from_id,
-                to_id,
-                amount: Balance::Cw20(Cw20Coin {
-                    address: token_address.clone(),
-                    amount: Uint128(1),
-                }),
-            },
+            propose("cold3", BackupAction::Burn {}),
         )
         .unwrap();
-        assert_eq!(0, res.messages.len());
-        assert_eq!(attr("action", "transfer"), res.attributes[0]);
+        let info = mock_info(&signers[1], &[]);
+        handle(
+            &mut deps,
+            env.clone(),
+            info,
+            propose("cold3", BackupAction::Refresh {}),
+        )
+        .unwrap();
+        let res = query_list_proposals(&deps, None, None).unwrap();
+        let ids: Vec<u64> = res.proposals.iter().map(|p| p.id).collect();
+        assert_eq!(ids, vec![5, 6]);
 
-        // ensure the details is what we expect
-        let details = query_details(&deps, "foo".to_string()).unwrap();
-        assert_eq!(
-            details,
-            DetailsResponse {
-                id: "foo".to_string(),
-                backup: HumanAddr::from("backup"),
-                holder: HumanAddr::from("foo"),
-                clawback_period: mock_clawback_period,
-                end_time: mock_time + mock_clawback_period,
-                native_balance: vec![],
-                cw20_balance: vec![Cw20CoinHuman {
-                    address: HumanAddr::from("my-cw20-token"),
-                    amount: Uint128(99),
-                }],
-                cw20_whitelist: vec![
-                    HumanAddr::from("other-token"),
-                    HumanAddr::from("my-cw20-token")
-                ],
-            }
-        );
+        // open proposals are removed with their clawback
+        let mut late_env = env;
+        late_env.block.time = mock_time + 101;
+        let info = mock_info(&create.holder, &[]);
+        let msg = HandleMsg::Withdraw {
+            id: "cold3".to_string(),
+            recipient: None,
+            msg: None,
+        };
+        handle(&mut deps, late_env, info, msg).unwrap();
+        let res = query_list_proposals(&deps, None, None).unwrap();
+        assert_eq!(res.proposals.len(), 0);
+        query_proposal(&deps, 5).unwrap_err();
+    }
+
+    #[test]
+    fn update_holder_and_backup() {
+        let (create, mock_time, mock_clawback_period, mut deps, _) = basic_native_setup();
+        let mut env = mock_env();
+        env.block.time = mock_time;
+        let id = create.id.clone().unwrap();
+        let ids_of = |deps: &Extern<_, _, _>, msg: QueryMsg| {
+            let res: ListResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            res.clawbacks
+        };
+        let by_holder = |holder: &str| QueryMsg::ByHolder {
+            holder: HumanAddr::from(holder),
+            start_after: None,
+            limit: None,
+        };
+        let by_backup = |backup: &str| QueryMsg::ByBackup {
+            backup: HumanAddr::from(backup),
+            start_after: None,
+            limit: None,
+        };
+        let update_holder = |holder: &str| HandleMsg::UpdateHolder {
+            id: id.clone(),
+            holder: HumanAddr::from(holder),
+        };
+
+        let info = mock_info(HumanAddr::from("stranger"), &[]);
+        let err = handle(&mut deps, env.clone(), info, update_holder("stranger")).unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        // the backup may replace the holder within the period
+        let info = mock_info(&create.backup, &[]);
+        let res = handle(&mut deps, env.clone(), info, update_holder("holder2")).unwrap();
+        assert_eq!(attr("action", "update_holder"), res.attributes[0]);
+        assert_eq!(attr("from", "holder"), res.attributes[2]);
+        assert_eq!(attr("to", "holder2"), res.attributes[3]);
+        assert_eq!(ids_of(&deps, by_holder("holder")), Vec::<String>::new());
+        assert_eq!(ids_of(&deps, by_holder("holder2")), vec![id.clone()]);
+
+        // but not after it
+        let mut new_env = env.clone();
+        new_env.block.time = mock_time + mock_clawback_period + 1;
+        let info = mock_info(&create.backup, &[]);
+        let err = handle(&mut deps, new_env.clone(), info, update_holder("holder3")).unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
 
-        let details = query_details(&deps, "bar".to_string()).unwrap();
+        // the holder may hand it over, which restarts the period
+        let info = mock_info(HumanAddr::from("holder2"), &[]);
+        handle(&mut deps, new_env.clone(), info, update_holder("holder3")).unwrap();
+        let details = query_details(&deps, mock_env(), id.clone()).unwrap();
+        assert_eq!(details.holder, HumanAddr::from("holder3"));
         assert_eq!(
-            details,
-            DetailsResponse {
-                id: "bar".to_string(),
-                backup: HumanAddr::from("backup"),
-                holder: HumanAddr::from("bar"),
-                clawback_period: mock_clawback_period,
-                end_time: mock_time + 1 + mock_clawback_period,
-                native_balance: vec![],
-                cw20_balance: vec![Cw20CoinHuman {
-                    address: HumanAddr::from("my-cw20-token"),
-                    amount: Uint128(101),
-                }],
-                cw20_whitelist: vec![
-                    HumanAddr::from("other-token"),
-                    HumanAddr::from("my-cw20-token")
-                ],
-            }
+            details.end_time,
+            Expiration::AtTime(new_env.block.time + mock_clawback_period)
         );
 
-        // claw back
-        let from_id = "bar".to_string();
-        let to_id = "foo".to_string();
-        let info = mock_info(HumanAddr::from("backup"), &[]);
+        // only the backup proposes a new backup
+        let update_backup = HandleMsg::UpdateBackup {
+            id: id.clone(),
+            backup: HumanAddr::from("backup2"),
+        };
+        let info = mock_info(HumanAddr::from("holder3"), &[]);
+        let err = handle(&mut deps, new_env.clone(), info, update_backup.clone()).unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        let info = mock_info(&create.backup, &[]);
+        handle(&mut deps, new_env.clone(), info, update_backup).unwrap();
+        let details = query_details(&deps, mock_env(), id.clone()).unwrap();
+        assert_eq!(details.backup, create.backup);
+        assert_eq!(details.pending_backup, Some(HumanAddr::from("backup2")));
+
+        // and only the proposed one accepts
+        let accept = HandleMsg::AcceptBackup { id: id.clone() };
+        let info = mock_info(HumanAddr::from("stranger"), &[]);
+        let err = handle(&mut deps, new_env.clone(), info, accept.clone()).unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        let info = mock_info(HumanAddr::from("backup2"), &[]);
+        let res = handle(&mut deps, new_env.clone(), info, accept).unwrap();
+        assert_eq!(attr("action", "accept_backup"), res.attributes[0]);
+        assert_eq!(attr("from", "backup"), res.attributes[2]);
+        assert_eq!(attr("to", "backup2"), res.attributes[3]);
+        let details = query_details(&deps, mock_env(), id.clone()).unwrap();
+        assert_eq!(details.backup, HumanAddr::from("backup2"));
+        assert_eq!(details.pending_backup, None);
+        assert_eq!(ids_of(&deps, by_backup("backup")), Vec::<String>::new());
+        assert_eq!(ids_of(&deps, by_backup("backup2")), vec![id.clone()]);
+
+        // the old backup has no say anymore
+        let info = mock_info(&create.backup, &[]);
+        let burn = HandleMsg::Burn { id: id.clone() };
+        let err = handle(&mut deps, new_env.clone(), info, burn.clone()).unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        let info = mock_info(HumanAddr::from("backup2"), &[]);
+        handle(&mut deps, new_env, info, burn).unwrap();
+    }
+
+    #[test]
+    fn clawback_to_recovery_address() {
+        let (create, mock_time, mock_clawback_period, mut deps, _) = basic_native_setup();
+        let mut env = mock_env();
+        env.block.time = mock_time;
+        let id = create.id.clone().unwrap();
+        let clawback = |amount: Option<Vec<Coin>>| HandleMsg::Clawback {
+            id: id.clone(),
+            recipient: HumanAddr::from("recovery"),
+            amount: amount.map(Balance::from),
+        };
+
+        // only the backup
+        let info = mock_info(&create.holder, &[]);
+        let err = handle(&mut deps, env.clone(), info, clawback(None)).unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        // some of the tokens
+        let info = mock_info(&create.backup, &[]);
         let res = handle(
             &mut deps,
-            new_env.clone(),
-            info,
-            HandleMsg::ClawbackTransfer {
-                from_id,
-                to_id,
-                amount: Balance::Cw20(Cw20Coin {
-                    address: token_address.clone(),
-                    amount: Uint128(1),
-                }),
-            },
+            env.clone(),
+            info.clone(),
+            clawback(Some(coins(30, "tokens"))),
         )
         .unwrap();
-        assert_eq!(0, res.messages.len());
-        assert_eq!(attr("action", "transfer"), res.attributes[0]);
+        assert_eq!(attr("action", "clawback"), res.attributes[0]);
+        assert_eq!(
+            res.messages,
+            vec![CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: HumanAddr::from("recovery"),
+                amount: coins(30, "tokens"),
+            })]
+        );
+        let details = query_details(&deps, mock_env(), id.clone()).unwrap();
+        assert_eq!(details.native_balance, coins(70, "tokens"));
 
-        // ensure the details is what we expect
-        let details = query_details(&deps, "foo".to_string()).unwrap();
+        let err = handle(
+            &mut deps,
+            env.clone(),
+            info.clone(),
+            clawback(Some(coins(71, "tokens"))),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::NotEnoughBalance {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        // not after the period
+        let mut late_env = env.clone();
+        late_env.block.time = mock_time + mock_clawback_period + 1;
+        let err = handle(&mut deps, late_env, info.clone(), clawback(None)).unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        // the rest, which closes the clawback
+        let res = handle(&mut deps, env.clone(), info, clawback(None)).unwrap();
         assert_eq!(
-            details,
-            DetailsResponse {
-                id: "foo".to_string(),
-                backup: HumanAddr::from("backup"),
-                holder: HumanAddr::from("foo"),
-                clawback_period: mock_clawback_period,
-                end_time: mock_time + 1 + mock_clawback_period,
-                native_balance: vec![],
-                cw20_balance: vec![Cw20CoinHuman {
-                    address: HumanAddr::from("my-cw20-token"),
-                    amount: Uint128(100),
-                }],
-                cw20_whitelist: vec![
-                    HumanAddr::from("other-token"),
-                    HumanAddr::from("my-cw20-token")
-                ]
-            }
+            res.messages,
+            vec![CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address,
+                to_address: HumanAddr::from("recovery"),
+                amount: coins(70, "tokens"),
+            })]
         );
+        query_details(&deps, mock_env(), id).unwrap_err();
+    }
+
+    #[test]
+    fn transfer_to_new_clawback() {
+        let (create, mock_time, mock_clawback_period, mut deps, _) = basic_native_setup();
+        let mut env = mock_env();
+        env.block.time = mock_time;
+        let transfer = |to_id: &str, period: u64, create_to: bool| HandleMsg::ClawbackTransfer {
+            from_id: create.id.clone().unwrap(),
+            to_id: to_id.to_string(),
+            amount: Balance::from(coins(40, "tokens")),
+            create: if create_to {
+                Some(TransferCreateMsg {
+                    holder: HumanAddr::from("payee"),
+                    clawback_period: Duration::Time(period),
+                })
+            } else {
+                None
+            },
+        };
+        let info = mock_info(&create.holder, &[]);
 
-        let details = query_details(&deps, "bar".to_string()).unwrap();
+        // the target must exist unless it is created
+        handle(
+            &mut deps,
+            env.clone(),
+            info.clone(),
+            transfer("fresh", mock_clawback_period, false),
+        )
+        .unwrap_err();
+
+        // the period may not be shorter
+        let err = handle(
+            &mut deps,
+            env.clone(),
+            info.clone(),
+            transfer("fresh", mock_clawback_period - 1, true),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::ContractMismatch {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        let err = handle(
+            &mut deps,
+            env.clone(),
+            info.clone(),
+            transfer("x", mock_clawback_period, true),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::InvalidId {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        handle(
+            &mut deps,
+            env.clone(),
+            info.clone(),
+            transfer("fresh", mock_clawback_period + 10, true),
+        )
+        .unwrap();
+        let details = query_details(&deps, mock_env(), "fresh".to_string()).unwrap();
         assert_eq!(
             details,
             DetailsResponse {
-                id: "bar".to_string(),
-                backup: HumanAddr::from("backup"),
-                holder: HumanAddr::from("bar"),
-                clawback_period: mock_clawback_period,
-                end_time: mock_time + 1 + mock_clawback_period,
-                native_balance: vec![],
-                cw20_balance: vec![Cw20CoinHuman {
-                    address: HumanAddr::from("my-cw20-token"),
-                    amount: Uint128(100),
-                }],
-                cw20_whitelist: vec![
-                    HumanAddr::from("other-token"),
-                    HumanAddr::from("my-cw20-token")
-                ]
+                id: "fresh".to_string(),
+                backup: create.backup.clone(),
+                holder: HumanAddr::from("payee"),
+                end_time: Expiration::AtTime(mock_time + mock_clawback_period + 10),
+                clawback_period: Duration::Time(mock_clawback_period + 10),
+                native_balance: coins(40, "tokens"),
+                cw20_balance: vec![],
+                cw20_whitelist: vec![],
+                backup_multisig: None,
+                pending_backup: None,
+                refresh_policy: RefreshPolicy::default(),
+                max_end_time: None,
+                refreshes: 0,
+                release: ReleaseSchedule::Cliff {},
+                max_clawback: None,
+                arbiter: None,
+                dispute: None,
+                depositors: DepositPolicy::Anyone {},
+                native_whitelist: None,
+                unlisted_denoms: UnlistedDenoms::Reject {},
+                clawed_back_native: vec![],
+                clawed_back_cw20: vec![],
+                vested_native: vec![],
+                vested_cw20: vec![],
+                unvested_native: coins(40, "tokens"),
+                unvested_cw20: vec![],
             }
         );
+        let details = query_details(&deps, mock_env(), create.id.clone().unwrap()).unwrap();
+        assert_eq!(details.native_balance, coins(60, "tokens"));
 
-        // failures
-        let from_id = "foo".to_string();
-        let to_id = "wrong-per".to_string();
-        let info = mock_info(HumanAddr::from(from_id.clone()), &[]);
-        let res = handle(
+        // it cannot be created twice
+        let err = handle(
             &mut deps,
-            new_env.clone(),
+            env,
             info,
-            HandleMsg::ClawbackTransfer {
-                from_id,
-                to_id,
-                amount: Balance::Cw20(Cw20Coin {
-                    address: token_address.clone(),
-                    amount: Uint128(1),
-                }),
+            transfer("fresh", mock_clawback_period, true),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::AlreadyInUse {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn transfer_remote() {
+        let (create, mock_time, _, mut deps, balance) = basic_native_setup();
+        let mut env = mock_env();
+        env.block.time = mock_time;
+        let token = HumanAddr::from("my-cw20-token");
+
+        // a cw20 clawback with the same terms
+        let cw20_create = CreateMsg {
+            id: Some("cw20src".to_string()),
+            ..create.clone()
+        };
+        let receive = Cw20ReceiveMsg {
+            sender: HumanAddr::from("source"),
+            amount: Uint128(100),
+            msg: Some(to_binary(&ReceiveMsg::Create(cw20_create)).unwrap()),
+        };
+        let info = mock_info(&token, &[]);
+        handle(&mut deps, env.clone(), info, HandleMsg::Receive(receive)).unwrap();
+
+        // the other contract
+        let mut remote = mock_dependencies(&[]);
+        let info = mock_info(HumanAddr::from("anyone"), &[]);
+        init(
+            &mut remote,
+            env.clone(),
+            info,
+            InitMsg {
+                native_sink: None,
+                admin: None,
+                emergency_policy: None,
+                allowlist: None,
+                max_tokens: None,
+                peers: Some(vec![HumanAddr::from(MOCK_CONTRACT_ADDR)]),
             },
-        );
+        )
+        .unwrap();
+        for (id, backup) in [("target", "backup"), ("other", "backup2")].iter() {
+            let msg = CreateMsg {
+                id: Some(id.to_string()),
+                backup: HumanAddr::from(*backup),
+                clawback_period: Duration::Time(10),
+                cw20_whitelist: Some(vec![token.clone()]),
+                ..create.clone()
+            };
+            let info = mock_info(HumanAddr::from("source"), &balance);
+            handle(&mut remote, env.clone(), info, HandleMsg::Create(msg)).unwrap();
+        }
 
-        match res.unwrap_err() {
-            ContractError::ContractMismatch {} => {}
-            e => panic!("Expected ContractMismatch, got {}", e),
+        // the tokens only go to a peer
+        let transfer = HandleMsg::TransferRemote {
+            from_id: create.id.clone().unwrap(),
+            contract: HumanAddr::from("holder-own-contract"),
+            to_id: "target".to_string(),
+            amount: Balance::from(coins(40, "tokens")),
+        };
+        let info = mock_info(&create.holder, &[]);
+        let err = handle(&mut deps, env.clone(), info, transfer).unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
         }
+        let details = query_details(&deps, mock_env(), create.id.clone().unwrap()).unwrap();
+        assert_eq!(details.native_balance, coins(100, "tokens"));
+        let contract = HumanAddr::from("remote-contract");
+        let info = mock_info(HumanAddr::from("admin"), &[]);
+        let msg = HandleMsg::UpdatePeers {
+            peers: vec![contract.clone()],
+        };
+        handle(&mut deps, env.clone(), info, msg).unwrap();
 
-        let from_id = "foo".to_string();
-        let to_id = "wrong-back".to_string();
-        let info = mock_info(HumanAddr::from(from_id.clone()), &[]);
-        let res = handle(
-            &mut deps,
-            new_env.clone(),
-            info,
-            HandleMsg::ClawbackTransfer {
-                from_id,
-                to_id,
-                amount: Balance::Cw20(Cw20Coin {
-                    address: token_address.clone(),
-                    amount: Uint128(1),
-                }),
+        // native tokens go with a TransferIn
+        let transfer = HandleMsg::TransferRemote {
+            from_id: create.id.clone().unwrap(),
+            contract: contract.clone(),
+            to_id: "target".to_string(),
+            amount: Balance::from(coins(40, "tokens")),
+        };
+        let info = mock_info(&create.holder, &[]);
+        let res = handle(&mut deps, env.clone(), info, transfer).unwrap();
+        assert_eq!(attr("action", "transfer_remote"), res.attributes[0]);
+        let details = query_details(&deps, mock_env(), create.id.clone().unwrap()).unwrap();
+        assert_eq!(details.native_balance, coins(60, "tokens"));
+        let transfer_in = match &res.messages[..] {
+            [CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr,
+                msg,
+                send,
+            })] => {
+                assert_eq!(contract_addr, &contract);
+                assert_eq!(send, &coins(40, "tokens"));
+                from_binary::<HandleMsg>(msg).unwrap()
+            }
+            m => panic!("unexpected messages: {:?}", m),
+        };
+
+        // it is rejected by a clawback with another backup
+        let terms = match &transfer_in {
+            HandleMsg::TransferIn { terms, .. } => terms.clone(),
+            m => panic!("unexpected message: {:?}", m),
+        };
+        // only a peer may send it
+        let info = mock_info(HumanAddr::from("mallory"), &coins(40, "tokens"));
+        let err = handle(&mut remote, env.clone(), info, transfer_in.clone()).unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        let info = mock_info(MOCK_CONTRACT_ADDR, &coins(40, "tokens"));
+        let mismatch = HandleMsg::TransferIn {
+            id: "other".to_string(),
+            terms: terms.clone(),
+        };
+        let err = handle(&mut remote, env.clone(), info.clone(), mismatch).unwrap_err();
+        match err {
+            ContractError::ContractMismatch {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        // or a shorter period
+        let mismatch = HandleMsg::TransferIn {
+            id: "target".to_string(),
+            terms: TransferTerms {
+                clawback_period: Duration::Time(11),
+                ..terms
             },
+        };
+        let err = handle(&mut remote, env.clone(), info.clone(), mismatch).unwrap_err();
+        match err {
+            ContractError::ContractMismatch {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        handle(&mut remote, env.clone(), info, transfer_in).unwrap();
+        let details = query_details(&remote, mock_env(), "target".to_string()).unwrap();
+        assert_eq!(details.native_balance, coins(140, "tokens"));
+
+        // cw20 tokens go with a Send
+        let transfer = HandleMsg::TransferRemote {
+            from_id: "cw20src".to_string(),
+            contract: contract.clone(),
+            to_id: "target".to_string(),
+            amount: Balance::Cw20(Cw20Coin {
+                address: deps.api.canonical_address(&token).unwrap(),
+                amount: Uint128(7),
+            }),
+        };
+        let info = mock_info(&create.holder, &[]);
+        let res = handle(&mut deps, env.clone(), info, transfer).unwrap();
+        let send = match &res.messages[..] {
+            [CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr, msg, ..
+            })] => {
+                assert_eq!(contract_addr, &token);
+                from_binary::<Cw20HandleMsg>(msg).unwrap()
+            }
+            m => panic!("unexpected messages: {:?}", m),
+        };
+        let receive = match send {
+            Cw20HandleMsg::Send {
+                contract: to,
+                amount,
+                msg,
+            } => {
+                assert_eq!(to, contract);
+                Cw20ReceiveMsg {
+                    sender: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                    amount,
+                    msg,
+                }
+            }
+            m => panic!("unexpected message: {:?}", m),
+        };
+        // the cw20 tokens must come from a peer as well
+        let info = mock_info(&token, &[]);
+        let forged = Cw20ReceiveMsg {
+            sender: HumanAddr::from("mallory"),
+            ..receive.clone()
+        };
+        let err = handle(
+            &mut remote,
+            env.clone(),
+            info.clone(),
+            HandleMsg::Receive(forged),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        handle(&mut remote, env, info, HandleMsg::Receive(receive)).unwrap();
+        let details = query_details(&remote, mock_env(), "target".to_string()).unwrap();
+        assert_eq!(
+            details.cw20_balance,
+            vec![Cw20CoinHuman {
+                address: token,
+                amount: Uint128(7),
+            }]
+        );
+    }
+
+    #[test]
+    fn refresh_policy() {
+        let (create, mock_time, _, mut deps, balance) = basic_native_setup();
+        let mut env = mock_env();
+        env.block.time = mock_time;
+        let policies = [
+            (
+                "counted",
+                RefreshPolicy {
+                    max_refreshes: Some(1),
+                    ..RefreshPolicy::default()
+                },
+            ),
+            (
+                "capped",
+                RefreshPolicy {
+                    max_lock: Some(Duration::Time(5)),
+                    ..RefreshPolicy::default()
+                },
+            ),
+            (
+                "backup_only",
+                RefreshPolicy {
+                    refresh_by: RefreshBy::Backup,
+                    ..RefreshPolicy::default()
+                },
+            ),
+            (
+                "no_relock",
+                RefreshPolicy {
+                    holder_relock: false,
+                    ..RefreshPolicy::default()
+                },
+            ),
+            (
+                "holder_only",
+                RefreshPolicy {
+                    refresh_by: RefreshBy::Holder,
+                    ..RefreshPolicy::default()
+                },
+            ),
+        ];
+        for (id, policy) in policies.iter() {
+            let msg = CreateMsg {
+                id: Some(id.to_string()),
+                refresh_policy: Some(policy.clone()),
+                ..create.clone()
+            };
+            let info = mock_info(HumanAddr::from("source"), &balance);
+            handle(&mut deps, env.clone(), info, HandleMsg::Create(msg)).unwrap();
+        }
+        let details = query_details(&deps, mock_env(), "capped".to_string()).unwrap();
+        assert_eq!(details.refresh_policy, policies[1].1);
+        assert_eq!(
+            details.max_end_time,
+            Some(Expiration::AtTime(mock_time + 5))
         );
 
-        match res.unwrap_err() {
-            ContractError::ContractMismatch {} => {}
-            e => panic!("Expected ContractMismatch, got {}", e),
+        // max_lock is in the units of the period
+        let msg = CreateMsg {
+            id: Some("mixed".to_string()),
+            refresh_policy: Some(RefreshPolicy {
+                max_lock: Some(Duration::Height(5)),
+                ..RefreshPolicy::default()
+            }),
+            ..create.clone()
+        };
+        let info = mock_info(HumanAddr::from("source"), &balance);
+        let err = handle(&mut deps, env.clone(), info, HandleMsg::Create(msg)).unwrap_err();
+        match err {
+            ContractError::InvalidRefreshPolicy {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        let refresh = |deps: &mut Extern<_, _, _>, env: &Env, sender: &HumanAddr, id: &str| {
+            let info = mock_info(sender, &[]);
+            let msg = HandleMsg::Refresh { id: id.to_string() };
+            handle(deps, env.clone(), info, msg)
+        };
+        let assert_not_allowed = |res: Result<HandleResponse, ContractError>| match res {
+            Err(ContractError::RefreshNotAllowed {}) => {}
+            r => panic!("unexpected result: {:?}", r),
+        };
+
+        // only once, also by transfers
+        refresh(&mut deps, &env, &create.holder, "counted").unwrap();
+        assert_not_allowed(refresh(&mut deps, &env, &create.backup, "counted"));
+        let info = mock_info(&create.holder, &[]);
+        let transfer = HandleMsg::ClawbackTransfer {
+            from_id: "foobar".to_string(),
+            to_id: "counted".to_string(),
+            amount: Balance::from(coins(1, "tokens")),
+            create: None,
+        };
+        assert_not_allowed(handle(&mut deps, env.clone(), info, transfer));
+        let details = query_details(&deps, mock_env(), "counted".to_string()).unwrap();
+        assert_eq!(details.refreshes, 1);
+
+        // a handover by the holder then keeps the period
+        let mut later = env.clone();
+        later.block.time = mock_time + 1;
+        for id in ["counted", "backup_only"].iter() {
+            let before = query_details(&deps, mock_env(), id.to_string()).unwrap();
+            let info = mock_info(&create.holder, &[]);
+            let msg = HandleMsg::UpdateHolder {
+                id: id.to_string(),
+                holder: create.holder.clone(),
+            };
+            handle(&mut deps, later.clone(), info, msg).unwrap();
+            let details = query_details(&deps, mock_env(), id.to_string()).unwrap();
+            assert_eq!(details.end_time, before.end_time);
+            assert_eq!(details.refreshes, before.refreshes);
         }
+
+        // not past the max lock time
+        let mut later = env.clone();
+        later.block.time = mock_time + 4;
+        refresh(&mut deps, &later, &create.holder, "capped").unwrap();
+        later.block.time = mock_time + 5;
+        assert_not_allowed(refresh(&mut deps, &later, &create.holder, "capped"));
+
+        // by the allowed party only
+        assert_not_allowed(refresh(&mut deps, &env, &create.holder, "backup_only"));
+        refresh(&mut deps, &env, &create.backup, "backup_only").unwrap();
+
+        // the holder may not relock after expiry
+        let mut expired = env.clone();
+        expired.block.time = mock_time + 10;
+        assert_not_allowed(refresh(&mut deps, &expired, &create.holder, "no_relock"));
+        refresh(&mut deps, &env, &create.holder, "no_relock").unwrap();
+
+        // incoming transfers refresh only if made by an allowed party
+        let msg = CreateMsg {
+            id: Some("stranger".to_string()),
+            holder: HumanAddr::from("stranger"),
+            ..create.clone()
+        };
+        let info = mock_info(HumanAddr::from("source"), &balance);
+        handle(&mut deps, env.clone(), info, HandleMsg::Create(msg)).unwrap();
+        let transfer = |from_id: &str, to_id: &str| HandleMsg::ClawbackTransfer {
+            from_id: from_id.to_string(),
+            to_id: to_id.to_string(),
+            amount: Balance::from(coins(1, "tokens")),
+            create: None,
+        };
+        let stranger = mock_info(HumanAddr::from("stranger"), &[]);
+        let msg = transfer("stranger", "holder_only");
+        assert_not_allowed(handle(&mut deps, env.clone(), stranger.clone(), msg));
+        let msg = transfer("stranger", "backup_only");
+        assert_not_allowed(handle(&mut deps, env.clone(), stranger, msg));
+        let info = mock_info(&create.holder, &[]);
+        let msg = transfer("foobar", "holder_only");
+        handle(&mut deps, env.clone(), info.clone(), msg).unwrap();
+        let msg = transfer("foobar", "backup_only");
+        assert_not_allowed(handle(&mut deps, env.clone(), info, msg));
+        let info = mock_info(&create.backup, &[]);
+        let msg = transfer("stranger", "backup_only");
+        handle(&mut deps, env.clone(), info, msg).unwrap();
+
+        // also from other contracts
+        let info = mock_info(HumanAddr::from("admin"), &[]);
+        let msg = HandleMsg::UpdatePeers {
+            peers: vec![HumanAddr::from("remote-contract")],
+        };
+        handle(&mut deps, env.clone(), info, msg).unwrap();
+        let transfer_in = |sender: Option<HumanAddr>| HandleMsg::TransferIn {
+            id: "holder_only".to_string(),
+            terms: TransferTerms {
+                backup: create.backup.clone(),
+                backup_multisig: None,
+                clawback_period: create.clawback_period,
+                cw20_whitelist: vec![],
+                native_whitelist: None,
+                sender,
+            },
+        };
+        let info = mock_info(HumanAddr::from("remote-contract"), &coins(1, "tokens"));
+        let msg = transfer_in(None);
+        assert_not_allowed(handle(&mut deps, env.clone(), info.clone(), msg));
+        let msg = transfer_in(Some(HumanAddr::from("stranger")));
+        assert_not_allowed(handle(&mut deps, env.clone(), info.clone(), msg));
+        let msg = transfer_in(Some(create.holder.clone()));
+        handle(&mut deps, env, info, msg).unwrap();
+        assert_total_locked_invariant(&deps);
     }
 
     #[test]
-    fn transfer_native() {
-        let mut deps = mock_dependencies(&[]);
+    fn release_schedule() {
+        let (create, mock_time, _, mut deps, balance) = basic_native_setup();
+        let mut env = mock_env();
+        env.block.time = mock_time;
+        for (id, release) in [
+            ("cliff", ReleaseSchedule::Cliff {}),
+            ("linear", ReleaseSchedule::Linear {}),
+            ("stepped", ReleaseSchedule::Step { steps: 4 }),
+        ]
+        .iter()
+        {
+            let msg = CreateMsg {
+                id: Some(id.to_string()),
+                clawback_period: Duration::Time(100),
+                release: Some(release.clone()),
+                ..create.clone()
+            };
+            let info = mock_info(HumanAddr::from("source"), &balance);
+            handle(&mut deps, env.clone(), info, HandleMsg::Create(msg)).unwrap();
+        }
+        // a step must be at least one second / block
+        for steps in [0, 101].iter() {
+            let msg = CreateMsg {
+                id: Some("invalid".to_string()),
+                clawback_period: Duration::Time(100),
+                release: Some(ReleaseSchedule::Step { steps: *steps }),
+                ..create.clone()
+            };
+            let info = mock_info(HumanAddr::from("source"), &balance);
+            match handle(&mut deps, env.clone(), info, HandleMsg::Create(msg)).unwrap_err() {
+                ContractError::InvalidRelease {} => {}
+                e => panic!("Expected InvalidRelease, got {}", e),
+            }
+        }
+        let mut later = env.clone();
+        later.block.time = mock_time + 30;
 
-        // init an empty contract
-        let init_msg = InitMsg {};
-        let mock_clawback_period = 2;
-        let mock_time = 1571920875;
-        let mut init_env = mock_env();
-        init_env.block.time = mock_time;
+        // linear releases in proportion to the time passed
+        let details = query_details(&deps, later.clone(), "linear".to_string()).unwrap();
+        assert_eq!(details.release, ReleaseSchedule::Linear {});
+        assert_eq!(details.vested_native, coins(30, "tokens"));
+        assert_eq!(details.unvested_native, coins(70, "tokens"));
 
-        let info = mock_info(&HumanAddr::from("anyone"), &[]);
-        let res = init(&mut deps, init_env.clone(), info, init_msg).unwrap();
-        assert_eq!(0, res.messages.len());
-        let balance = coins(100, "tokens");
-        // create two clawbacks
-        for idc in ["foo", "bar", "wrong-per", "wrong-back"].iter() {
-            let create = CreateMsg {
-                id: idc.to_string(),
-                backup: if *idc == "wrong-back" {
-                    HumanAddr::from("backup2")
-                } else {
-                    HumanAddr::from("backup")
-                },
-                holder: HumanAddr::from(*idc),
-                clawback_period: if *idc == "wrong-per" {
-                    mock_clawback_period - 1
-                } else {
-                    mock_clawback_period
-                },
-                cw20_whitelist: None,
-            };
-            let sender = HumanAddr::from("source");
+        // steps release in whole parts only
+        let details = query_details(&deps, later.clone(), "stepped".to_string()).unwrap();
+        assert_eq!(details.vested_native, coins(25, "tokens"));
+        assert_eq!(details.unvested_native, coins(75, "tokens"));
 
-            let info = mock_info(&sender, &balance);
-            let msg = HandleMsg::Create(create.clone());
+        // a cliff releases nothing before the end
+        let info = mock_info(&create.holder, &[]);
+        let withdraw = |id: &str| HandleMsg::Withdraw {
+            id: id.to_string(),
+            recipient: None,
+            msg: None,
+        };
+        match handle(&mut deps, later.clone(), info.clone(), withdraw("cliff")).unwrap_err() {
+            ContractError::NotExpired {} => {}
+            e => panic!("Expected NotExpired, got {}", e),
+        }
 
-            let res = handle(&mut deps, init_env.clone(), info, msg).unwrap();
-            assert_eq!(0, res.messages.len());
-            assert_eq!(attr("action", "create"), res.attributes[0]);
+        // not more than the released part
+        let partial = |amount: u128| HandleMsg::WithdrawPartial {
+            id: "linear".to_string(),
+            amount: Balance::from(coins(amount, "tokens")),
+            recipient: create.holder.clone(),
+            msg: None,
+        };
+        match handle(&mut deps, later.clone(), info.clone(), partial(31)).unwrap_err() {
+            ContractError::NotExpired {} => {}
+            e => panic!("Expected NotExpired, got {}", e),
+        }
+        handle(&mut deps, later.clone(), info.clone(), partial(10)).unwrap();
 
-            // ensure the details is what we expect
-            let details = query_details(&deps, idc.to_string()).unwrap();
-            assert_eq!(
-                details,
-                DetailsResponse {
-                    id: idc.to_string(),
-                    backup: if *idc == "wrong-back" {
-                        HumanAddr::from("backup2")
-                    } else {
-                        HumanAddr::from("backup")
-                    },
-                    holder: HumanAddr::from(*idc),
-                    clawback_period: if *idc == "wrong-per" {
-                        mock_clawback_period - 1
-                    } else {
-                        mock_clawback_period
-                    },
-                    end_time: if *idc == "wrong-per" {
-                        mock_time + mock_clawback_period - 1
-                    } else {
-                        mock_time + mock_clawback_period
-                    },
-                    native_balance: balance.clone(),
-                    cw20_balance: vec![],
-                    cw20_whitelist: vec![],
-                }
-            );
+        // withdraw takes the rest of the released part
+        let res = handle(&mut deps, later.clone(), info.clone(), withdraw("linear")).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: create.holder.clone(),
+                amount: coins(20, "tokens"),
+            })]
+        );
+        let details = query_details(&deps, later.clone(), "linear".to_string()).unwrap();
+        assert_eq!(details.native_balance, coins(70, "tokens"));
+        assert_eq!(details.vested_native, vec![]);
+        match handle(&mut deps, later.clone(), info.clone(), withdraw("linear")).unwrap_err() {
+            ContractError::NotExpired {} => {}
+            e => panic!("Expected NotExpired, got {}", e),
         }
 
-        // transfer it
-        let from_id = "foo".to_string();
-        let to_id = "bar".to_string();
-        let info = mock_info(HumanAddr::from(from_id.clone()), &[]);
-        let mut new_env = mock_env();
-        new_env.block.time = mock_time + 1;
-        let res = handle(
-            &mut deps,
-            new_env.clone(),
-            info,
-            HandleMsg::ClawbackTransfer {
-                from_id,
-                to_id,
-                amount: Balance::Native(NativeBalance(coins(1, "tokens"))),
-            },
-        )
-        .unwrap();
-        assert_eq!(0, res.messages.len());
-        assert_eq!(attr("action", "transfer"), res.attributes[0]);
+        // the release goes on with the withdrawn tokens counted in
+        later.block.time = mock_time + 50;
+        let details = query_details(&deps, later.clone(), "linear".to_string()).unwrap();
+        assert_eq!(details.vested_native, coins(20, "tokens"));
+        assert_eq!(details.unvested_native, coins(50, "tokens"));
 
-        // ensure the details is what we expect
-        let details = query_details(&deps, "foo".to_string()).unwrap();
+        // the backup may still take the unreleased part, but not the released one
+        let info = mock_info(&create.backup, &[]);
+        let burn = HandleMsg::Burn {
+            id: "stepped".to_string(),
+        };
+        handle(&mut deps, later.clone(), info.clone(), burn.clone()).unwrap();
+        let details = query_details(&deps, later.clone(), "stepped".to_string()).unwrap();
+        assert_eq!(details.native_balance, coins(50, "tokens"));
+        assert_eq!(details.vested_native, coins(50, "tokens"));
+        match handle(&mut deps, later.clone(), info.clone(), burn).unwrap_err() {
+            ContractError::EmptyBalance {} => {}
+            e => panic!("Expected EmptyBalance, got {}", e),
+        }
+        let transfer = HandleMsg::ClawbackTransfer {
+            from_id: "linear".to_string(),
+            to_id: "cliff".to_string(),
+            amount: Balance::from(coins(51, "tokens")),
+            create: None,
+        };
+        match handle(&mut deps, later.clone(), info.clone(), transfer).unwrap_err() {
+            ContractError::NotEnoughBalance {} => {}
+            e => panic!("Expected NotEnoughBalance, got {}", e),
+        }
+        let clawback = HandleMsg::Clawback {
+            id: "linear".to_string(),
+            recipient: HumanAddr::from("recovery"),
+            amount: None,
+        };
+        let res = handle(&mut deps, later.clone(), info, clawback).unwrap();
         assert_eq!(
-            details,
-            DetailsResponse {
-                id: "foo".to_string(),
-                backup: HumanAddr::from("backup"),
-                holder: HumanAddr::from("foo"),
-                clawback_period: mock_clawback_period,
-                end_time: mock_time + mock_clawback_period,
-                native_balance: coins(99, "tokens"),
-                cw20_balance: vec![],
-                cw20_whitelist: vec![],
-            }
+            res.messages,
+            vec![CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("recovery"),
+                amount: coins(50, "tokens"),
+            })]
         );
+        assert_total_locked_invariant(&deps);
+
+        // what was taken back does not slow down the release of the rest
+        later.block.time = mock_time + 60;
+        let details = query_details(&deps, later.clone(), "linear".to_string()).unwrap();
+        assert_eq!(details.native_balance, coins(20, "tokens"));
+        assert_eq!(details.vested_native, coins(20, "tokens"));
+        assert_eq!(details.unvested_native, vec![]);
+
+        // everything after the end
+        later.block.time = mock_time + 101;
+        let details = query_details(&deps, later, "stepped".to_string()).unwrap();
+        assert_eq!(details.vested_native, coins(50, "tokens"));
+        assert_eq!(details.unvested_native, vec![]);
+    }
+
+    #[test]
+    fn refresh_keeps_released() {
+        let (create, mock_time, _, mut deps, balance) = basic_native_setup();
+        let mut env = mock_env();
+        env.block.time = mock_time;
+        let msg = CreateMsg {
+            id: Some("linear".to_string()),
+            clawback_period: Duration::Time(100),
+            release: Some(ReleaseSchedule::Linear {}),
+            ..create.clone()
+        };
+        let info = mock_info(HumanAddr::from("source"), &balance);
+        handle(&mut deps, env.clone(), info, HandleMsg::Create(msg)).unwrap();
+        let mut later = env;
+        later.block.time = mock_time + 90;
 
-        let details = query_details(&deps, "bar".to_string()).unwrap();
+        // a refresh by the backup restarts the release of the unreleased part only
+        let info = mock_info(&create.backup, &[]);
+        let refresh = HandleMsg::Refresh {
+            id: "linear".to_string(),
+        };
+        handle(&mut deps, later.clone(), info.clone(), refresh).unwrap();
+        let details = query_details(&deps, later.clone(), "linear".to_string()).unwrap();
+        assert_eq!(details.end_time, Expiration::AtTime(mock_time + 190));
+        assert_eq!(details.vested_native, coins(90, "tokens"));
+        assert_eq!(details.unvested_native, coins(10, "tokens"));
+        let burn = HandleMsg::Burn {
+            id: "linear".to_string(),
+        };
+        let res = handle(&mut deps, later.clone(), info, burn).unwrap();
         assert_eq!(
-            details,
-            DetailsResponse {
-                id: "bar".to_string(),
-                backup: HumanAddr::from("backup"),
-                holder: HumanAddr::from("bar"),
-                clawback_period: mock_clawback_period,
-                end_time: mock_time + 1 + mock_clawback_period,
-                native_balance: coins(101, "tokens"),
-                cw20_balance: vec![],
-                cw20_whitelist: vec![],
-            }
+            res.messages,
+            vec![CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("community_pool"),
+                amount: coins(10, "tokens"),
+            })]
         );
 
-        // claw back
-        let from_id = "bar".to_string();
-        let to_id = "foo".to_string();
-        let info = mock_info(HumanAddr::from("backup"), &[]);
-        let res = handle(
-            &mut deps,
-            new_env.clone(),
-            info,
-            HandleMsg::ClawbackTransfer {
-                from_id,
-                to_id,
-                amount: Balance::Native(NativeBalance(coins(1, "tokens"))),
-            },
-        )
-        .unwrap();
-        assert_eq!(0, res.messages.len());
-        assert_eq!(attr("action", "transfer"), res.attributes[0]);
+        // so does an incoming transfer, whose tokens are released over the new period
+        let msg = CreateMsg {
+            id: Some("feeder".to_string()),
+            holder: HumanAddr::from("stranger"),
+            clawback_period: Duration::Time(100),
+            ..create.clone()
+        };
+        let info = mock_info(HumanAddr::from("source"), &coins(50, "tokens"));
+        handle(&mut deps, later.clone(), info, HandleMsg::Create(msg)).unwrap();
+        let info = mock_info(HumanAddr::from("stranger"), &[]);
+        let transfer = HandleMsg::ClawbackTransfer {
+            from_id: "feeder".to_string(),
+            to_id: "linear".to_string(),
+            amount: Balance::from(coins(50, "tokens")),
+            create: None,
+        };
+        handle(&mut deps, later.clone(), info, transfer).unwrap();
+        let details = query_details(&deps, later.clone(), "linear".to_string()).unwrap();
+        assert_eq!(details.native_balance, coins(140, "tokens"));
+        assert_eq!(details.vested_native, coins(90, "tokens"));
+        assert_eq!(details.unvested_native, coins(50, "tokens"));
 
-        // ensure the details is what we expect
-        let details = query_details(&deps, "foo".to_string()).unwrap();
+        // the holder withdraws the released tokens at any time
+        let info = mock_info(&create.holder, &[]);
+        let withdraw = HandleMsg::Withdraw {
+            id: "linear".to_string(),
+            recipient: None,
+            msg: None,
+        };
+        let res = handle(&mut deps, later.clone(), info, withdraw).unwrap();
         assert_eq!(
-            details,
-            DetailsResponse {
-                id: "foo".to_string(),
-                backup: HumanAddr::from("backup"),
-                holder: HumanAddr::from("foo"),
-                clawback_period: mock_clawback_period,
-                end_time: mock_time + 1 + mock_clawback_period,
-                native_balance: balance.clone(),
-                cw20_balance: vec![],
-                cw20_whitelist: vec![],
-            }
+            res.messages,
+            vec![CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: create.holder.clone(),
+                amount: coins(90, "tokens"),
+            })]
         );
+        later.block.time = mock_time + 140;
+        let details = query_details(&deps, later, "linear".to_string()).unwrap();
+        assert_eq!(details.native_balance, coins(50, "tokens"));
+        assert_eq!(details.vested_native, coins(25, "tokens"));
+    }
 
-        let details = query_details(&deps, "bar".to_string()).unwrap();
+    #[test]
+    fn max_clawback() {
+        let (create, mock_time, _, mut deps, balance) = basic_native_setup();
+        let mut env = mock_env();
+        env.block.time = mock_time;
+        let msg = CreateMsg {
+            id: Some("capped".to_string()),
+            clawback_period: Duration::Time(100),
+            max_clawback: Some(ClawbackLimit {
+                native: coins(30, "tokens"),
+                cw20: vec![],
+            }),
+            ..create.clone()
+        };
+        let info = mock_info(HumanAddr::from("source"), &balance);
+        handle(&mut deps, env.clone(), info, HandleMsg::Create(msg)).unwrap();
+
+        let backup = mock_info(&create.backup, &[]);
+        let clawback = |amount: u128| HandleMsg::Clawback {
+            id: "capped".to_string(),
+            recipient: HumanAddr::from("recovery"),
+            amount: Some(Balance::from(coins(amount, "tokens"))),
+        };
+        let assert_exceeded = |res: Result<HandleResponse, ContractError>| match res {
+            Err(ContractError::ClawbackLimitExceeded {}) => {}
+            r => panic!("unexpected result: {:?}", r),
+        };
+
+        // up to the cap in total
+        handle(&mut deps, env.clone(), backup.clone(), clawback(20)).unwrap();
+        assert_exceeded(handle(&mut deps, env.clone(), backup.clone(), clawback(11)));
+        let details = query_details(&deps, env.clone(), "capped".to_string()).unwrap();
+        assert_eq!(details.native_balance, coins(80, "tokens"));
+        assert_eq!(details.clawed_back_native, coins(20, "tokens"));
         assert_eq!(
-            details,
-            DetailsResponse {
-                id: "bar".to_string(),
-                backup: HumanAddr::from("backup"),
-                holder: HumanAddr::from("bar"),
-                clawback_period: mock_clawback_period,
-                end_time: mock_time + 1 + mock_clawback_period,
-                native_balance: balance.clone(),
-                cw20_balance: vec![],
-                cw20_whitelist: vec![],
-            }
+            details.max_clawback,
+            Some(ClawbackLimit {
+                native: coins(30, "tokens"),
+                cw20: vec![],
+            })
         );
 
-        // failures
-        let from_id = "foo".to_string();
-        let to_id = "wrong-per".to_string();
-        let info = mock_info(HumanAddr::from(from_id.clone()), &[]);
-        let res = handle(
-            &mut deps,
-            new_env.clone(),
-            info,
-            HandleMsg::ClawbackTransfer {
-                from_id,
-                to_id,
-                amount: Balance::Native(NativeBalance(coins(1, "tokens"))),
-            },
+        // transfers by the backup count, and a new clawback inherits the cap
+        let transfer = |from_id: &str, to_id: &str, amount: u128| HandleMsg::ClawbackTransfer {
+            from_id: from_id.to_string(),
+            to_id: to_id.to_string(),
+            amount: Balance::from(coins(amount, "tokens")),
+            create: Some(TransferCreateMsg {
+                holder: HumanAddr::from("other"),
+                clawback_period: Duration::Time(100),
+            }),
+        };
+        let msg = transfer("capped", "overflow", 20);
+        assert_exceeded(handle(&mut deps, env.clone(), backup.clone(), msg));
+        let msg = transfer("capped", "capped-2", 10);
+        handle(&mut deps, env.clone(), backup.clone(), msg).unwrap();
+        let details = query_details(&deps, env.clone(), "capped-2".to_string()).unwrap();
+        assert_eq!(details.max_clawback.unwrap().native, coins(30, "tokens"));
+        assert_eq!(details.clawed_back_native, vec![]);
+
+        // transfers by the holder do not
+        let holder = mock_info(&create.holder, &[]);
+        let msg = transfer("capped", "capped-3", 40);
+        handle(&mut deps, env.clone(), holder, msg).unwrap();
+        let details = query_details(&deps, env.clone(), "capped".to_string()).unwrap();
+        assert_eq!(details.native_balance, coins(30, "tokens"));
+        assert_eq!(details.clawed_back_native, coins(30, "tokens"));
+
+        // nothing more to burn
+        let msg = HandleMsg::Burn {
+            id: "capped".to_string(),
+        };
+        assert_exceeded(handle(&mut deps, env.clone(), backup.clone(), msg));
+
+        // replacing the holder counts the whole balance
+        let update_holder = |id: &str| HandleMsg::UpdateHolder {
+            id: id.to_string(),
+            holder: HumanAddr::from("recovery"),
+        };
+        let msg = update_holder("capped-3");
+        assert_exceeded(handle(&mut deps, env.clone(), backup.clone(), msg));
+        let other = mock_info(HumanAddr::from("other"), &[]);
+        let msg = transfer("capped-3", "capped-4", 25);
+        handle(&mut deps, env.clone(), other, msg).unwrap();
+        let msg = update_holder("capped-4");
+        handle(&mut deps, env.clone(), backup.clone(), msg).unwrap();
+        let details = query_details(&deps, env.clone(), "capped-4".to_string()).unwrap();
+        assert_eq!(details.holder, HumanAddr::from("recovery"));
+        assert_eq!(details.clawed_back_native, coins(25, "tokens"));
+
+        // tokens not in the cap cannot be taken back at all
+        let info = mock_info(HumanAddr::from("source"), &coins(5, "other"));
+        let msg = HandleMsg::TopUp {
+            id: "capped-2".to_string(),
+        };
+        handle(&mut deps, env.clone(), info, msg).unwrap();
+        let msg = HandleMsg::Clawback {
+            id: "capped-2".to_string(),
+            recipient: HumanAddr::from("recovery"),
+            amount: None,
+        };
+        assert_exceeded(handle(&mut deps, env, backup, msg));
+        assert_total_locked_invariant(&deps);
+    }
+
+    #[test]
+    fn dispute() {
+        let (create, mock_time, _, mut deps, balance) = basic_native_setup();
+        let mut env = mock_env();
+        env.block.time = mock_time;
+        for id in ["judged", "dismissed"].iter() {
+            let msg = CreateMsg {
+                id: Some(id.to_string()),
+                clawback_period: Duration::Time(100),
+                arbiter: Some(HumanAddr::from("arbiter")),
+                ..create.clone()
+            };
+            let info = mock_info(HumanAddr::from("source"), &balance);
+            handle(&mut deps, env.clone(), info, HandleMsg::Create(msg)).unwrap();
+        }
+        let holder = mock_info(&create.holder, &[]);
+        let backup = mock_info(&create.backup, &[]);
+        let arbiter = mock_info(HumanAddr::from("arbiter"), &[]);
+        let dispute = |id: &str| HandleMsg::Dispute {
+            id: id.to_string(),
+            reason: "order was delivered".to_string(),
+        };
+        let burn = |id: &str| HandleMsg::Burn { id: id.to_string() };
+
+        // only with an arbiter, by the holder, within the period
+        let mut expired = env.clone();
+        expired.block.time = mock_time + 2;
+        match handle(&mut deps, expired, holder.clone(), dispute("foobar")).unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            e => panic!("Expected Unauthorized, got {}", e),
+        }
+        match handle(&mut deps, env.clone(), holder.clone(), dispute("foobar")).unwrap_err() {
+            ContractError::NoArbiter {} => {}
+            e => panic!("Expected NoArbiter, got {}", e),
+        }
+        match handle(&mut deps, env.clone(), backup.clone(), dispute("judged")).unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            e => panic!("Expected Unauthorized, got {}", e),
+        }
+        // a new backup proposed before the dispute
+        let update_backup = HandleMsg::UpdateBackup {
+            id: "dismissed".to_string(),
+            backup: HumanAddr::from("backup2"),
+        };
+        handle(&mut deps, env.clone(), backup.clone(), update_backup).unwrap();
+        for id in ["judged", "dismissed"].iter() {
+            let res = handle(&mut deps, env.clone(), holder.clone(), dispute(id)).unwrap();
+            assert_eq!(attr("action", "dispute"), res.attributes[0]);
+            assert_eq!(attr("reason", "order was delivered"), res.attributes[2]);
+        }
+        let details = query_details(&deps, env.clone(), "judged".to_string()).unwrap();
+        assert_eq!(details.arbiter, Some(HumanAddr::from("arbiter")));
+        assert_eq!(
+            details.dispute,
+            Some(Dispute {
+                reason: "order was delivered".to_string(),
+                resolved: false,
+            })
         );
 
-        match res.unwrap_err() {
-            ContractError::ContractMismatch {} => {}
-            e => panic!("Expected ContractMismatch, got {}", e),
+        // the backup is stopped
+        match handle(&mut deps, env.clone(), backup.clone(), burn("judged")).unwrap_err() {
+            ContractError::Disputed {} => {}
+            e => panic!("Expected Disputed, got {}", e),
+        }
+        let transfer = HandleMsg::ClawbackTransfer {
+            from_id: "judged".to_string(),
+            to_id: "dismissed".to_string(),
+            amount: Balance::from(coins(10, "tokens")),
+            create: None,
+        };
+        match handle(&mut deps, env.clone(), backup.clone(), transfer).unwrap_err() {
+            ContractError::Disputed {} => {}
+            e => panic!("Expected Disputed, got {}", e),
+        }
+        let update_backup = HandleMsg::UpdateBackup {
+            id: "judged".to_string(),
+            backup: HumanAddr::from("backup2"),
+        };
+        match handle(&mut deps, env.clone(), backup.clone(), update_backup).unwrap_err() {
+            ContractError::Disputed {} => {}
+            e => panic!("Expected Disputed, got {}", e),
+        }
+        // nor accepted
+        let accept = HandleMsg::AcceptBackup {
+            id: "dismissed".to_string(),
+        };
+        let info = mock_info(HumanAddr::from("backup2"), &[]);
+        match handle(&mut deps, env.clone(), info, accept).unwrap_err() {
+            ContractError::Disputed {} => {}
+            e => panic!("Expected Disputed, got {}", e),
+        }
+
+        // only the arbiter resolves
+        let resolve = |id: &str, outcome: DisputeOutcome| HandleMsg::Resolve {
+            id: id.to_string(),
+            outcome,
+        };
+        let msg = resolve("judged", DisputeOutcome::Holder {});
+        match handle(&mut deps, env.clone(), backup.clone(), msg.clone()).unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            e => panic!("Expected Unauthorized, got {}", e),
         }
 
-        let from_id = "foo".to_string();
-        let to_id = "wrong-back".to_string();
-        let info = mock_info(HumanAddr::from(from_id.clone()), &[]);
-        let res = handle(
-            &mut deps,
-            new_env.clone(),
-            info,
-            HandleMsg::ClawbackTransfer {
-                from_id,
-                to_id,
-                amount: Balance::Native(NativeBalance(coins(1, "tokens"))),
-            },
+        // not while withdrawals are paused
+        let admin = mock_info(HumanAddr::from("admin"), &[]);
+        let pause = HandleMsg::Pause {
+            emergency_policy: Some(EmergencyPolicy {
+                backup_clawback: true,
+                holder_withdraw: false,
+            }),
+        };
+        handle(&mut deps, env.clone(), admin.clone(), pause).unwrap();
+        match handle(&mut deps, env.clone(), arbiter.clone(), msg.clone()).unwrap_err() {
+            ContractError::Paused {} => {}
+            e => panic!("Expected Paused, got {}", e),
+        }
+        handle(&mut deps, env.clone(), admin, HandleMsg::Unpause {}).unwrap();
+
+        // for the holder, who gets the tokens
+        let res = handle(&mut deps, env.clone(), arbiter.clone(), msg).unwrap();
+        assert_eq!(attr("outcome", "holder"), res.attributes[2]);
+        assert_eq!(
+            res.messages,
+            vec![CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: create.holder.clone(),
+                amount: balance.clone(),
+            })]
         );
+        query_details(&deps, env.clone(), "judged".to_string()).unwrap_err();
 
-        match res.unwrap_err() {
-            ContractError::ContractMismatch {} => {}
-            e => panic!("Expected ContractMismatch, got {}", e),
+        // for the backup, who may act again
+        let msg = resolve("dismissed", DisputeOutcome::Backup {});
+        let res = handle(&mut deps, env.clone(), arbiter.clone(), msg.clone()).unwrap();
+        assert_eq!(attr("outcome", "backup"), res.attributes[2]);
+        match handle(&mut deps, env.clone(), arbiter, msg).unwrap_err() {
+            ContractError::NotDisputed {} => {}
+            e => panic!("Expected NotDisputed, got {}", e),
+        }
+        match handle(&mut deps, env.clone(), holder, dispute("dismissed")).unwrap_err() {
+            ContractError::AlreadyDisputed {} => {}
+            e => panic!("Expected AlreadyDisputed, got {}", e),
         }
+        handle(&mut deps, env, backup, burn("dismissed")).unwrap();
+        assert_total_locked_invariant(&deps);
     }
 
     #[test]
-    fn refresh() {
-        let (create, mock_time, mock_clawback_period, mut deps, balance) = basic_native_setup();
+    fn pause() {
+        let (create, mock_time, _, mut deps, balance) = basic_native_setup();
+        let mut env = mock_env();
+        env.block.time = mock_time;
+        let long = CreateMsg {
+            id: Some("long".to_string()),
+            clawback_period: Duration::Time(100),
+            ..create.clone()
+        };
+        let info = mock_info(HumanAddr::from("source"), &balance);
+        handle(&mut deps, env.clone(), info, HandleMsg::Create(long)).unwrap();
 
-        // refresh it
-        let id = create.id.clone();
-        let info = mock_info(&create.holder, &[]);
-        let mut new_env = mock_env();
-        new_env.block.time = mock_time + mock_clawback_period;
-        let res = handle(
-            &mut deps,
-            new_env.clone(),
-            info,
-            HandleMsg::Refresh { id: id.clone() },
-        )
-        .unwrap();
-        assert_eq!(0, res.messages.len());
-        assert_eq!(attr("action", "refresh"), res.attributes[0]);
-        let details = query_details(&deps, id.clone()).unwrap();
+        // only by the admin
+        let policy = EmergencyPolicy {
+            backup_clawback: true,
+            holder_withdraw: false,
+        };
+        let msg = HandleMsg::Pause {
+            emergency_policy: Some(policy.clone()),
+        };
+        let info = mock_info(&create.backup, &[]);
+        match handle(&mut deps, env.clone(), info, msg.clone()).unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            e => panic!("Expected Unauthorized, got {}", e),
+        }
+        let admin = mock_info(HumanAddr::from("admin"), &[]);
+        let res = handle(&mut deps, env.clone(), admin.clone(), msg).unwrap();
+        assert_eq!(attr("action", "pause"), res.attributes[0]);
+        let cfg = query_config(&deps).unwrap();
         assert_eq!(
-            details,
-            DetailsResponse {
-                id,
-                backup: HumanAddr::from("backup"),
-                holder: HumanAddr::from("holder"),
-                clawback_period: mock_clawback_period,
-                end_time: mock_time + 2 * mock_clawback_period,
-                native_balance: balance.clone(),
-                cw20_balance: vec![],
-                cw20_whitelist: vec![],
+            cfg,
+            ConfigResponse {
+                admin: Some(HumanAddr::from("admin")),
+                native_sink: Some(HumanAddr::from("community_pool")),
+                paused: true,
+                emergency_policy: policy,
+                allowlist: None,
+                max_tokens: None,
+                peers: vec![],
             }
         );
+
+        let assert_paused = |res: Result<HandleResponse, ContractError>| match res {
+            Err(ContractError::Paused {}) => {}
+            r => panic!("unexpected result: {:?}", r),
+        };
+        // no creation, top-ups or transfers
+        let new = CreateMsg {
+            id: Some("new".to_string()),
+            ..create.clone()
+        };
+        let info = mock_info(HumanAddr::from("source"), &balance);
+        let msg = HandleMsg::Create(new.clone());
+        assert_paused(handle(&mut deps, env.clone(), info.clone(), msg));
+        let msg = HandleMsg::TopUp {
+            id: "long".to_string(),
+        };
+        assert_paused(handle(&mut deps, env.clone(), info, msg));
+        let holder = mock_info(&create.holder, &[]);
+        let msg = HandleMsg::ClawbackTransfer {
+            from_id: "foobar".to_string(),
+            to_id: "long".to_string(),
+            amount: Balance::from(coins(10, "tokens")),
+            create: None,
+        };
+        assert_paused(handle(&mut deps, env.clone(), holder.clone(), msg));
+
+        // withdrawals and clawbacks as the emergency policy says
+        let mut expired = env.clone();
+        expired.block.time = mock_time + 2;
+        let msg = HandleMsg::Withdraw {
+            id: "foobar".to_string(),
+            recipient: None,
+            msg: None,
+        };
+        assert_paused(handle(
+            &mut deps,
+            expired.clone(),
+            holder.clone(),
+            msg.clone(),
+        ));
+        let backup = mock_info(&create.backup, &[]);
+        let clawback = HandleMsg::Clawback {
+            id: "long".to_string(),
+            recipient: HumanAddr::from("recovery"),
+            amount: None,
+        };
+        handle(&mut deps, env.clone(), backup, clawback).unwrap();
+
+        // all back to normal
+        let res = handle(&mut deps, env.clone(), admin, HandleMsg::Unpause {}).unwrap();
+        assert_eq!(attr("action", "unpause"), res.attributes[0]);
+        assert!(!query_config(&deps).unwrap().paused);
+        handle(&mut deps, expired, holder, msg).unwrap();
+        let info = mock_info(HumanAddr::from("source"), &balance);
+        handle(&mut deps, env, info, HandleMsg::Create(new)).unwrap();
+        assert_total_locked_invariant(&deps);
     }
 
     #[test]
-    fn burn() {
-        let (create, mock_time, _, mut deps, _) = basic_native_setup();
+    fn token_allowlist() {
+        let (create, mock_time, _, mut deps, balance) = basic_native_setup();
+        let mut env = mock_env();
+        env.block.time = mock_time;
+        let allowlist = TokenAllowlist {
+            native: vec!["tokens".to_string()],
+            cw20: vec![HumanAddr::from("my-cw20-token")],
+        };
+        let msg = HandleMsg::UpdateAllowlist {
+            allowlist: Some(allowlist.clone()),
+            max_tokens: Some(2),
+        };
 
-        // burn it
-        let id = create.id.clone();
+        // only by the admin
         let info = mock_info(&create.backup, &[]);
-        let mut new_env = mock_env();
-        new_env.block.time = mock_time;
-        let res = handle(&mut deps, new_env.clone(), info, HandleMsg::Burn { id }).unwrap();
-        // FIXME: for native tokens, some bank/supply message back to burn in Cosmos SDK?
-        assert_eq!(0, res.messages.len());
-        assert_eq!(attr("action", "burn"), res.attributes[0]);
+        match handle(&mut deps, env.clone(), info, msg.clone()).unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            e => panic!("Expected Unauthorized, got {}", e),
+        }
+        let admin = mock_info(HumanAddr::from("admin"), &[]);
+        let res = handle(&mut deps, env.clone(), admin.clone(), msg).unwrap();
+        assert_eq!(attr("action", "update_allowlist"), res.attributes[0]);
+        let cfg = query_config(&deps).unwrap();
+        assert_eq!(cfg.allowlist, Some(allowlist));
+        assert_eq!(cfg.max_tokens, Some(2));
 
-        // second attempt fails (not found)
-        let id = create.id.clone();
-        let info = mock_info(&create.backup, &[]);
-        let res = handle(&mut deps, new_env, info, HandleMsg::Burn { id });
-        match res.unwrap_err() {
-            ContractError::Std(StdError::NotFound { .. }) => {}
-            e => panic!("Expected NotFound, got {}", e),
+        let assert_not_allowed = |res: Result<HandleResponse, ContractError>| match res {
+            Err(ContractError::TokenNotAllowed {}) => {}
+            r => panic!("unexpected result: {:?}", r),
+        };
+        // no other tokens sent or whitelisted
+        let mixed = CreateMsg {
+            id: Some("mixed".to_string()),
+            cw20_whitelist: Some(vec![HumanAddr::from("my-cw20-token")]),
+            ..create.clone()
+        };
+        let info = mock_info(HumanAddr::from("source"), &coins(10, "dust"));
+        let msg = HandleMsg::Create(mixed.clone());
+        assert_not_allowed(handle(&mut deps, env.clone(), info, msg));
+        let msg = HandleMsg::Create(CreateMsg {
+            cw20_whitelist: Some(vec![HumanAddr::from("other-token")]),
+            ..mixed.clone()
+        });
+        let info = mock_info(HumanAddr::from("source"), &balance);
+        assert_not_allowed(handle(&mut deps, env.clone(), info, msg));
+        let info = mock_info(HumanAddr::from("source"), &coins(10, "dust"));
+        let top_up = HandleMsg::TopUp {
+            id: "foobar".to_string(),
+        };
+        assert_not_allowed(handle(&mut deps, env.clone(), info, top_up.clone()));
+        let receive = Cw20ReceiveMsg {
+            sender: HumanAddr::from("source"),
+            amount: Uint128(100),
+            msg: Some(
+                to_binary(&ReceiveMsg::TopUp {
+                    id: "mixed".to_string(),
+                })
+                .unwrap(),
+            ),
+        };
+        let info = mock_info(HumanAddr::from("source"), &balance);
+        handle(&mut deps, env.clone(), info, HandleMsg::Create(mixed)).unwrap();
+        let info = mock_info(HumanAddr::from("other-token"), &[]);
+        let msg = HandleMsg::Receive(receive.clone());
+        assert_not_allowed(handle(&mut deps, env.clone(), info, msg));
+
+        // up to the limit of different tokens
+        let info = mock_info(HumanAddr::from("my-cw20-token"), &[]);
+        handle(&mut deps, env.clone(), info, HandleMsg::Receive(receive)).unwrap();
+        let msg = HandleMsg::UpdateAllowlist {
+            allowlist: Some(TokenAllowlist {
+                native: vec!["tokens".to_string(), "dust".to_string()],
+                cw20: vec![HumanAddr::from("my-cw20-token")],
+            }),
+            max_tokens: Some(2),
+        };
+        handle(&mut deps, env.clone(), admin.clone(), msg).unwrap();
+        let info = mock_info(HumanAddr::from("source"), &coins(10, "dust"));
+        let msg = HandleMsg::TopUp {
+            id: "mixed".to_string(),
+        };
+        match handle(&mut deps, env.clone(), info.clone(), msg).unwrap_err() {
+            ContractError::TooManyTokens { max: 2 } => {}
+            e => panic!("Expected TooManyTokens, got {}", e),
         }
+        handle(&mut deps, env.clone(), info, top_up.clone()).unwrap();
+
+        // lifted
+        let msg = HandleMsg::UpdateAllowlist {
+            allowlist: None,
+            max_tokens: None,
+        };
+        handle(&mut deps, env.clone(), admin, msg).unwrap();
+        let info = mock_info(HumanAddr::from("source"), &coins(10, "other"));
+        handle(&mut deps, env, info, top_up).unwrap();
+        assert_total_locked_invariant(&deps);
     }
 
     #[test]
-    fn top_up_mixed_tokens() {
-        let mut deps = mock_dependencies(&[]);
-        let mock_time = 1571920875;
-        let mut init_env = mock_env();
-        init_env.block.time = mock_time;
-        // init an empty contract
-        let init_msg = InitMsg {};
-        let info = mock_info(&HumanAddr::from("anyone"), &[]);
-        let res = init(&mut deps, init_env.clone(), info, init_msg).unwrap();
-        assert_eq!(0, res.messages.len());
+    fn depositors() {
+        let (create, mock_time, _, mut deps, balance) = basic_native_setup();
+        let mut env = mock_env();
+        env.block.time = mock_time;
+        let listed = DepositPolicy::Only {
+            depositors: vec![HumanAddr::from("payer")],
+        };
+        for (id, depositors) in [
+            ("ours", DepositPolicy::HolderAndBackup {}),
+            ("listed", listed.clone()),
+        ]
+        .iter()
+        {
+            let msg = CreateMsg {
+                id: Some(id.to_string()),
+                cw20_whitelist: Some(vec![HumanAddr::from("my-cw20-token")]),
+                depositors: Some(depositors.clone()),
+                ..create.clone()
+            };
+            let info = mock_info(HumanAddr::from("source"), &balance);
+            handle(&mut deps, env.clone(), info, HandleMsg::Create(msg)).unwrap();
+        }
+        let details = query_details(&deps, env.clone(), "listed".to_string()).unwrap();
+        assert_eq!(details.depositors, listed);
 
-        // only accept these tokens
-        let whitelist = vec![HumanAddr::from("bar_token"), HumanAddr::from("foo_token")];
+        // only the holder and the backup
+        let top_up = HandleMsg::TopUp {
+            id: "ours".to_string(),
+        };
+        let info = mock_info(HumanAddr::from("source"), &balance);
+        match handle(&mut deps, env.clone(), info, top_up.clone()).unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            e => panic!("Expected Unauthorized, got {}", e),
+        }
+        for sender in [&create.holder, &create.backup].iter() {
+            let info = mock_info(sender, &balance);
+            let res = handle(&mut deps, env.clone(), info, top_up.clone()).unwrap();
+            assert_eq!(attr("action", "top_up"), res.attributes[0]);
+            assert_eq!(attr("sender", sender), res.attributes[2]);
+        }
 
-        // create a clawback with 2 native tokens
-        let mock_clawback_period = 1;
+        // only the listed ones, also with cw20 tokens
+        let receive = |sender: &str| Cw20ReceiveMsg {
+            sender: HumanAddr::from(sender),
+            amount: Uint128(100),
+            msg: Some(
+                to_binary(&ReceiveMsg::TopUp {
+                    id: "listed".to_string(),
+                })
+                .unwrap(),
+            ),
+        };
+        let info = mock_info(HumanAddr::from("my-cw20-token"), &[]);
+        let msg = HandleMsg::Receive(receive(create.holder.as_str()));
+        match handle(&mut deps, env.clone(), info.clone(), msg).unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            e => panic!("Expected Unauthorized, got {}", e),
+        }
+        let msg = HandleMsg::Receive(receive("payer"));
+        let res = handle(&mut deps, env.clone(), info, msg).unwrap();
+        assert_eq!(attr("sender", "payer"), res.attributes[2]);
 
-        let create = CreateMsg {
-            id: "foobar".to_string(),
-            backup: HumanAddr::from("backup"),
-            holder: HumanAddr::from("holder"),
-            clawback_period: mock_clawback_period,
-            cw20_whitelist: Some(whitelist),
+        // the same goes for transfers from other clawbacks
+        let msg = CreateMsg {
+            id: Some("stranger".to_string()),
+            holder: HumanAddr::from("stranger"),
+            ..create.clone()
         };
-        let sender = HumanAddr::from("source");
-        let balance = vec![coin(100, "fee"), coin(200, "stake")];
-        let info = mock_info(&sender, &balance);
-        let msg = HandleMsg::Create(create.clone());
-        let res = handle(&mut deps, init_env.clone(), info, msg).unwrap();
-        assert_eq!(0, res.messages.len());
-        assert_eq!(attr("action", "create"), res.attributes[0]);
+        let info = mock_info(HumanAddr::from("source"), &balance);
+        handle(&mut deps, env.clone(), info, HandleMsg::Create(msg)).unwrap();
+        let transfer = |from_id: &str| HandleMsg::ClawbackTransfer {
+            from_id: from_id.to_string(),
+            to_id: "ours".to_string(),
+            amount: Balance::from(coins(1, "tokens")),
+            create: None,
+        };
+        let info = mock_info(HumanAddr::from("stranger"), &[]);
+        match handle(&mut deps, env.clone(), info, transfer("stranger")).unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            e => panic!("Expected Unauthorized, got {}", e),
+        }
+        let info = mock_info(&create.holder, &[]);
+        handle(&mut deps, env.clone(), info, transfer("foobar")).unwrap();
 
-        // top it up with 2 more native tokens
-        let extra_native = vec![coin(250, "random"), coin(300, "stake")];
-        let info = mock_info(&sender, &extra_native);
-        let top_up = HandleMsg::TopUp {
-            id: create.id.clone(),
+        // and from other contracts
+        let info = mock_info(HumanAddr::from("admin"), &[]);
+        let msg = HandleMsg::UpdatePeers {
+            peers: vec![HumanAddr::from("remote-contract")],
         };
-        let res = handle(&mut deps, init_env.clone(), info, top_up).unwrap();
-        assert_eq!(0, res.messages.len());
-        assert_eq!(attr("action", "top_up"), res.attributes[0]);
+        handle(&mut deps, env.clone(), info, msg).unwrap();
+        let transfer_in = |sender: Option<HumanAddr>| HandleMsg::TransferIn {
+            id: "listed".to_string(),
+            terms: TransferTerms {
+                backup: create.backup.clone(),
+                backup_multisig: None,
+                clawback_period: create.clawback_period,
+                cw20_whitelist: vec![],
+                native_whitelist: None,
+                sender,
+            },
+        };
+        let info = mock_info(HumanAddr::from("remote-contract"), &coins(1, "tokens"));
+        for sender in [None, Some(create.holder.clone())].iter() {
+            let msg = transfer_in(sender.clone());
+            match handle(&mut deps, env.clone(), info.clone(), msg).unwrap_err() {
+                ContractError::Unauthorized {} => {}
+                e => panic!("Expected Unauthorized, got {}", e),
+            }
+        }
+        let msg = transfer_in(Some(HumanAddr::from("payer")));
+        handle(&mut deps, env.clone(), info, msg).unwrap();
 
-        // top up with one foreign token
-        let bar_token = HumanAddr::from("bar_token");
-        let base = TopUp {
-            id: create.id.clone(),
+        // anyone by default
+        let info = mock_info(HumanAddr::from("stranger"), &balance);
+        let msg = HandleMsg::TopUp {
+            id: "foobar".to_string(),
         };
-        let top_up = HandleMsg::Receive(Cw20ReceiveMsg {
-            sender: HumanAddr::from("random"),
-            amount: Uint128(7890),
-            msg: Some(to_binary(&base).unwrap()),
-        });
-        let info = mock_info(&bar_token, &[]);
-        let res = handle(&mut deps, init_env.clone(), info, top_up).unwrap();
-        assert_eq!(0, res.messages.len());
-        assert_eq!(attr("action", "top_up"), res.attributes[0]);
+        handle(&mut deps, env, info, msg).unwrap();
+        assert_total_locked_invariant(&deps);
+    }
 
-        // top with a foreign token not on the whitelist
-        // top up with one foreign token
-        let baz_token = HumanAddr::from("baz_token");
-        let base = TopUp {
-            id: create.id.clone(),
+    #[test]
+    fn native_whitelist() {
+        let (create, mock_time, _, mut deps, _) = basic_native_setup();
+        let mut env = mock_env();
+        env.block.time = mock_time;
+        let sent = vec![coin(100, "tokens"), coin(5, "fee")];
+        let strict = CreateMsg {
+            id: Some("strict".to_string()),
+            native_whitelist: Some(vec!["tokens".to_string()]),
+            ..create.clone()
         };
-        let top_up = HandleMsg::Receive(Cw20ReceiveMsg {
-            sender: HumanAddr::from("random"),
-            amount: Uint128(7890),
-            msg: Some(to_binary(&base).unwrap()),
-        });
-        let info = mock_info(&baz_token, &[]);
-        let res = handle(&mut deps, init_env.clone(), info, top_up);
-        match res.unwrap_err() {
+        let refunding = CreateMsg {
+            id: Some("refunding".to_string()),
+            unlisted_denoms: Some(UnlistedDenoms::Refund {}),
+            ..strict.clone()
+        };
+
+        // unlisted denoms are rejected by default
+        let info = mock_info(HumanAddr::from("source"), &sent);
+        let msg = HandleMsg::Create(strict.clone());
+        match handle(&mut deps, env.clone(), info, msg).unwrap_err() {
             ContractError::NotInWhitelist {} => {}
-            e => panic!("Unexpected error: {}", e),
+            e => panic!("Expected NotInWhitelist, got {}", e),
+        }
+        let info = mock_info(HumanAddr::from("source"), &coins(100, "tokens"));
+        handle(&mut deps, env.clone(), info, HandleMsg::Create(strict)).unwrap();
+        let info = mock_info(HumanAddr::from("source"), &coins(5, "fee"));
+        let msg = HandleMsg::TopUp {
+            id: "strict".to_string(),
+        };
+        match handle(&mut deps, env.clone(), info, msg).unwrap_err() {
+            ContractError::NotInWhitelist {} => {}
+            e => panic!("Expected NotInWhitelist, got {}", e),
         }
 
-        // top up with second foreign token
-        let foo_token = HumanAddr::from("foo_token");
-        let base = TopUp {
-            id: create.id.clone(),
+        // or sent back
+        let refund = vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("source"),
+            amount: coins(5, "fee"),
+        })];
+        let info = mock_info(HumanAddr::from("source"), &sent);
+        let res = handle(
+            &mut deps,
+            env.clone(),
+            info.clone(),
+            HandleMsg::Create(refunding),
+        )
+        .unwrap();
+        assert_eq!(res.messages, refund);
+        let top_up = HandleMsg::TopUp {
+            id: "refunding".to_string(),
         };
-        let top_up = HandleMsg::Receive(Cw20ReceiveMsg {
-            sender: HumanAddr::from("random"),
-            amount: Uint128(888),
-            msg: Some(to_binary(&base).unwrap()),
-        });
-        let info = mock_info(&foo_token, &[]);
-        let res = handle(&mut deps, init_env.clone(), info, top_up).unwrap();
-        assert_eq!(0, res.messages.len());
-        assert_eq!(attr("action", "top_up"), res.attributes[0]);
+        let res = handle(&mut deps, env.clone(), info, top_up.clone()).unwrap();
+        assert_eq!(res.messages, refund);
+        let details = query_details(&deps, env.clone(), "refunding".to_string()).unwrap();
+        assert_eq!(details.native_balance, coins(200, "tokens"));
+        assert_eq!(details.native_whitelist, Some(vec!["tokens".to_string()]));
+        assert_eq!(details.unlisted_denoms, UnlistedDenoms::Refund {});
 
-        // withdraw it
-        let mut new_env = mock_env();
-        new_env.block.time = mock_time + mock_clawback_period + 1;
-        let id = create.id.clone();
+        // but not everything
+        let info = mock_info(HumanAddr::from("source"), &coins(5, "fee"));
+        match handle(&mut deps, env, info, top_up).unwrap_err() {
+            ContractError::EmptyBalance {} => {}
+            e => panic!("Expected EmptyBalance, got {}", e),
+        }
+        assert_total_locked_invariant(&deps);
+    }
+
+    #[test]
+    fn transfer_whitelists() {
+        let (create, mock_time, _, mut deps, _) = basic_native_setup();
+        let mut env = mock_env();
+        env.block.time = mock_time;
+        let sent = vec![coin(100, "tokens"), coin(100, "other")];
+        for (id, native_whitelist) in [
+            ("wide", None),
+            ("narrow", Some(vec!["tokens".to_string()])),
+            ("narrow-2", Some(vec!["tokens".to_string()])),
+        ]
+        .iter()
+        {
+            let msg = CreateMsg {
+                id: Some(id.to_string()),
+                clawback_period: Duration::Time(100),
+                native_whitelist: native_whitelist.clone(),
+                ..create.clone()
+            };
+            let coins = if native_whitelist.is_some() {
+                sent[..1].to_vec()
+            } else {
+                sent.clone()
+            };
+            let info = mock_info(HumanAddr::from("source"), &coins);
+            handle(&mut deps, env.clone(), info, HandleMsg::Create(msg)).unwrap();
+        }
         let info = mock_info(&create.holder, &[]);
-        let res = handle(&mut deps, new_env.clone(), info, HandleMsg::Withdraw { id }).unwrap();
-        assert_eq!(attr("action", "withdraw"), res.attributes[0]);
-        assert_eq!(3, res.messages.len());
+        let transfer =
+            |from_id: &str, to_id: &str, amount: Vec<Coin>| HandleMsg::ClawbackTransfer {
+                from_id: from_id.to_string(),
+                to_id: to_id.to_string(),
+                amount: Balance::from(amount),
+                create: None,
+            };
+        let assert_not_whitelisted = |res: Result<HandleResponse, ContractError>| match res {
+            Err(ContractError::NotInWhitelist {}) => {}
+            r => panic!("unexpected result: {:?}", r),
+        };
 
-        // first message releases all native coins
-        assert_eq!(
-            res.messages[0],
-            CosmosMsg::Bank(BankMsg::Send {
-                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
-                to_address: create.holder.clone(),
-                amount: vec![coin(100, "fee"), coin(500, "stake"), coin(250, "random")],
-            })
-        );
+        // not from a clawback that takes any denom to one that does not
+        let msg = transfer("wide", "narrow", coins(10, "tokens"));
+        assert_not_whitelisted(handle(&mut deps, env.clone(), info.clone(), msg));
+        let msg = transfer("narrow", "wide", coins(10, "tokens"));
+        handle(&mut deps, env.clone(), info.clone(), msg).unwrap();
 
-        // second one release bar cw20 token
-        let send_msg = Cw20HandleMsg::Transfer {
-            recipient: create.holder.clone(),
-            amount: Uint128(7890),
-        };
-        assert_eq!(
-            res.messages[1],
-            CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: bar_token,
-                msg: to_binary(&send_msg).unwrap(),
-                send: vec![],
-            })
-        );
+        // nor tokens the receiving clawback does not take
+        let msg = transfer("narrow", "narrow-2", coins(10, "other"));
+        assert_not_whitelisted(handle(&mut deps, env.clone(), info.clone(), msg));
+        let msg = transfer("narrow", "narrow-2", coins(10, "tokens"));
+        handle(&mut deps, env.clone(), info, msg).unwrap();
+        let details = query_details(&deps, env.clone(), "narrow-2".to_string()).unwrap();
+        assert_eq!(details.native_balance, coins(110, "tokens"));
 
-        // third one release foo cw20 token
-        let send_msg = Cw20HandleMsg::Transfer {
-            recipient: create.holder.clone(),
-            amount: Uint128(888),
+        // the same from another contract, once the admin made it a peer
+        let update = HandleMsg::UpdatePeers {
+            peers: vec![HumanAddr::from("remote-contract")],
         };
-        assert_eq!(
-            res.messages[2],
-            CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: foo_token,
-                msg: to_binary(&send_msg).unwrap(),
-                send: vec![],
-            })
-        );
+        let info = mock_info(HumanAddr::from("remote-contract"), &[]);
+        match handle(&mut deps, env.clone(), info, update.clone()).unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            e => panic!("Expected Unauthorized, got {}", e),
+        }
+        let info = mock_info(HumanAddr::from("admin"), &[]);
+        handle(&mut deps, env.clone(), info, update).unwrap();
+        let cfg = query_config(&deps).unwrap();
+        assert_eq!(cfg.peers, vec![HumanAddr::from("remote-contract")]);
+        let terms = TransferTerms {
+            backup: create.backup.clone(),
+            backup_multisig: None,
+            clawback_period: Duration::Time(100),
+            cw20_whitelist: vec![],
+            native_whitelist: None,
+            sender: Some(create.holder.clone()),
+        };
+        let info = mock_info(HumanAddr::from("remote-contract"), &coins(10, "tokens"));
+        let msg = HandleMsg::TransferIn {
+            id: "narrow".to_string(),
+            terms: terms.clone(),
+        };
+        match handle(&mut deps, env.clone(), info, msg).unwrap_err() {
+            ContractError::ContractMismatch {} => {}
+            e => panic!("Expected ContractMismatch, got {}", e),
+        }
+        let msg = HandleMsg::TransferIn {
+            id: "narrow".to_string(),
+            terms: TransferTerms {
+                native_whitelist: Some(vec!["tokens".to_string()]),
+                ..terms
+            },
+        };
+        let info = mock_info(HumanAddr::from("remote-contract"), &coins(10, "other"));
+        assert_not_whitelisted(handle(&mut deps, env.clone(), info, msg.clone()));
+        let info = mock_info(HumanAddr::from("remote-contract"), &coins(10, "tokens"));
+        handle(&mut deps, env, info, msg).unwrap();
+        assert_total_locked_invariant(&deps);
     }
 }
@@ -24,9 +24,69 @@ pub enum ContractError {
     #[error("A clawback does not contain enough coins for transfer")]
     NotEnoughBalance {},
 
+    #[error("The backup cannot take back more than the max_clawback of the clawback")]
+    ClawbackLimitExceeded {},
+
     #[error("Incoming clawback's backup and period should match the outgoing one")]
     ContractMismatch {},
 
     #[error("Clawback id already in use")]
     AlreadyInUse {},
+
+    #[error("Clawback id must be 3-20 characters of ascii letters, digits, '-' or '_'")]
+    InvalidId {},
+
+    #[error("Refresh is not allowed by the refresh policy")]
+    RefreshNotAllowed {},
+
+    #[error("The max_lock of the refresh policy must be in the units of the clawback period")]
+    InvalidRefreshPolicy {},
+
+    #[error("A stepped release must have between 1 and as many steps as the clawback period")]
+    InvalidRelease {},
+
+    #[error("Multisig threshold must be between 1 and the number of distinct signers")]
+    InvalidThreshold {},
+
+    #[error("Clawback has no backup multisig")]
+    NoMultisig {},
+
+    #[error("Proposal expired")]
+    ProposalExpired {},
+
+    #[error("Already voted on this proposal")]
+    AlreadyVoted {},
+
+    #[error("Clawback has no arbiter")]
+    NoArbiter {},
+
+    #[error("Clawback was already disputed")]
+    AlreadyDisputed {},
+
+    #[error("Backup actions are stopped while the clawback is disputed")]
+    Disputed {},
+
+    #[error("Clawback is not disputed")]
+    NotDisputed {},
+
+    #[error("Contract is paused")]
+    Paused {},
+
+    #[error("Token is not on the allowlist of the contract")]
+    TokenNotAllowed {},
+
+    #[error("A clawback may hold at most {max} different tokens")]
+    TooManyTokens { max: u32 },
+
+    #[error("Native tokens cannot be burned without a configured native_sink")]
+    NoNativeSink {},
+
+    #[error("Cannot migrate from a different contract: {previous_contract}")]
+    CannotMigrate { previous_contract: String },
+
+    #[error("Cannot migrate from version {previous_version}")]
+    CannotMigrateVersion { previous_version: String },
+
+    #[error("Migration not finished, migrate again to convert the remaining clawbacks")]
+    MigrationPending {},
 }
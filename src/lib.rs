@@ -4,4 +4,4 @@ pub mod msg;
 pub mod state;
 
 #[cfg(all(target_arch = "wasm32", not(feature = "library")))]
-cosmwasm_std::create_entry_points!(contract);
+cosmwasm_std::create_entry_points_with_migration!(contract);
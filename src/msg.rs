@@ -1,36 +1,127 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Api, CanonicalAddr, Coin, HumanAddr, StdResult};
+use cosmwasm_std::{Api, Binary, CanonicalAddr, Coin, HumanAddr, StdResult};
 
+use cw0::{Duration, Expiration};
 use cw20::{Balance, Cw20CoinHuman, Cw20ReceiveMsg};
 
 #[derive(Serialize, Deserialize, JsonSchema)]
-pub struct InitMsg {}
+pub struct InitMsg {
+    /// the address that receives native tokens of burned clawbacks
+    /// (e.g. a community pool or an unspendable address);
+    /// if not set, clawbacks holding native tokens cannot be burned
+    pub native_sink: Option<HumanAddr>,
+    /// the address that may pause the contract (see HandleMsg::Pause);
+    /// if not set, the contract cannot be paused
+    pub admin: Option<HumanAddr>,
+    /// what is still allowed while the contract is paused (all but creation,
+    /// top-ups and transfers if not set)
+    pub emergency_policy: Option<EmergencyPolicy>,
+    /// the only tokens clawbacks accept (any if not set)
+    pub allowlist: Option<TokenAllowlist>,
+    /// the most different tokens a clawback may hold (no limit if not set)
+    pub max_tokens: Option<u32>,
+    /// the clawback contracts that may TransferIn to this one and TransferRemote
+    /// may send to (none if not set)
+    pub peers: Option<Vec<HumanAddr>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TokenAllowlist {
+    /// the native denoms clawbacks accept
+    pub native: Vec<String>,
+    /// the cw20 token contracts clawbacks accept (and may whitelist)
+    pub cw20: Vec<HumanAddr>,
+}
+
+/// The actions allowed while the contract is paused.
+/// Creation, top-ups and transfers are always stopped.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct EmergencyPolicy {
+    /// the backup may still burn or claw back the tokens
+    pub backup_clawback: bool,
+    /// the holder may still withdraw the tokens
+    pub holder_withdraw: bool,
+}
+
+impl Default for EmergencyPolicy {
+    fn default() -> Self {
+        EmergencyPolicy {
+            backup_clawback: true,
+            holder_withdraw: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct MigrateMsg {
+    /// if set, replaces the configured "native_sink"
+    /// (contracts from before 0.4 have none)
+    pub native_sink: Option<HumanAddr>,
+    /// if set, replaces the configured "admin"
+    pub admin: Option<HumanAddr>,
+    /// the most clawbacks of an older layout to convert in this migration (100 if not set,
+    /// at least 1, at most 500); if more are left, migrate again to continue
+    pub limit: Option<u32>,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum HandleMsg {
     Create(CreateMsg),
     /// Adds all sent native tokens to the contract
+    /// (if the sender may deposit, see CreateMsg::depositors)
     TopUp {
         id: String,
     },
-    /// Sends all tokens to the holder (after end time).
+    /// Sends all tokens to the holder or the given recipient (after end time),
+    /// or the released part of them before that (see ReleaseSchedule).
     Withdraw {
         /// id is a human-readable name for the clawback from create
         id: String,
+        /// the address that receives the tokens (the holder if not set)
+        recipient: Option<HumanAddr>,
+        /// if set, cw20 tokens are sent with `Cw20HandleMsg::Send` and this message,
+        /// so that the recipient contract can act on them
+        msg: Option<Binary>,
+    },
+    /// Sends the given amount of tokens to the recipient (after end time,
+    /// or out of the released part before that).
+    /// The rest stays in the clawback, which is deleted once it is empty.
+    WithdrawPartial {
+        /// id is a human-readable name for the clawback from create
+        id: String,
+        /// the amount of the token(s) to withdraw
+        amount: Balance,
+        /// the address that receives the tokens
+        recipient: HumanAddr,
+        /// if set, cw20 tokens are sent with `Cw20HandleMsg::Send` and this message,
+        /// so that the recipient contract can act on them
+        msg: Option<Binary>,
     },
     /// Updates the end time with the extra clawback_period
     Refresh {
         /// id is a human-readable name for the clawback from create
         id: String,
     },
-    /// Destroys the tokens
+    /// Destroys the tokens: cw20 tokens are burned,
+    /// native tokens are sent to the configured "native_sink"
     Burn {
         /// id is a human-readable name for the clawback from create
         id: String,
     },
+    /// Sends some or all tokens to a recovery address (within the clawback period, by the backup).
+    /// The rest stays in the clawback, which is deleted once it is empty.
+    Clawback {
+        /// id is a human-readable name for the clawback from create
+        id: String,
+        /// the address that receives the tokens
+        recipient: HumanAddr,
+        /// the amount of the token(s) to take back (all if not set)
+        amount: Option<Balance>,
+    },
     /// Transfer is only allowed between the clawbacks with the same
     /// "backup", "clawback_period" and "cw20_whitelist"
     ClawbackTransfer {
@@ -40,26 +131,211 @@ pub enum HandleMsg {
         to_id: String,
         /// the amount of the token(s) to transfer
         amount: Balance,
+        /// if set, "to_id" is created with these terms (and the backup and whitelist
+        /// of "from_id"); it must not exist yet
+        create: Option<TransferCreateMsg>,
+    },
+    /// Replaces the holder. The backup may do so within the clawback period,
+    /// the holder may hand the clawback over at any time, which restarts the period
+    /// if the refresh policy lets the holder refresh
+    UpdateHolder {
+        /// id is a human-readable name for the clawback from create
+        id: String,
+        /// the new holder
+        holder: HumanAddr,
+    },
+    /// Proposes a new backup key (within the clawback period),
+    /// which takes over once it accepts with AcceptBackup
+    UpdateBackup {
+        /// id is a human-readable name for the clawback from create
+        id: String,
+        /// the new backup
+        backup: HumanAddr,
+    },
+    /// Makes the sender the backup (acting alone, without a multisig),
+    /// if it was proposed with UpdateBackup and the clawback is not disputed
+    AcceptBackup {
+        /// id is a human-readable name for the clawback from create
+        id: String,
+    },
+    /// Transfers to a clawback in another instance of this contract,
+    /// which checks that its terms match the outgoing clawback (see ClawbackTransfer);
+    /// the contract must be one of the peers of this contract
+    TransferRemote {
+        /// id is a human-readable name for the clawback from create
+        from_id: String,
+        /// the address of the other clawback contract
+        contract: HumanAddr,
+        /// id of the clawback in the other contract
+        to_id: String,
+        /// the amount of the token(s) to transfer
+        amount: Balance,
+    },
+    /// Adds the sent native tokens from a TransferRemote to the clawback,
+    /// if the terms of the outgoing clawback match it;
+    /// only by one of the peers of this contract
+    TransferIn {
+        /// id is a human-readable name for the clawback from create
+        id: String,
+        terms: TransferTerms,
+    },
+    /// Proposes a backup action on a clawback with a "backup_multisig"
+    /// (counts as the vote of the sender, which must be one of the signers).
+    /// The proposal can pass until the current end of the clawback period.
+    Propose {
+        /// id is a human-readable name for the clawback from create
+        id: String,
+        action: BackupAction,
+    },
+    /// Votes for a proposal, the action is executed once the threshold is reached
+    Vote {
+        proposal_id: u64,
+    },
+    /// Lets the holder dispute the clawback within the clawback period,
+    /// which stops all backup actions until the arbiter resolves it.
+    /// There can be only one dispute per clawback.
+    Dispute {
+        /// id is a human-readable name for the clawback from create
+        id: String,
+        reason: String,
+    },
+    /// Resolves the dispute of a clawback, only by its arbiter
+    Resolve {
+        /// id is a human-readable name for the clawback from create
+        id: String,
+        outcome: DisputeOutcome,
+    },
+    /// Stops creation, top-ups and transfers in all clawbacks, and everything else
+    /// that the emergency policy does not allow; only by the admin
+    Pause {
+        /// if set, replaces the configured emergency policy
+        emergency_policy: Option<EmergencyPolicy>,
+    },
+    /// Lifts a Pause; only by the admin
+    Unpause {},
+    /// Replaces the token allowlist and the limit on different tokens per clawback
+    /// (unset lifts them); only by the admin.
+    /// Clawbacks keep the tokens they already hold.
+    UpdateAllowlist {
+        allowlist: Option<TokenAllowlist>,
+        max_tokens: Option<u32>,
+    },
+    /// Replaces the clawback contracts that may TransferIn to this one and TransferRemote
+    /// may send to; only by the admin
+    UpdatePeers {
+        peers: Vec<HumanAddr>,
     },
     /// This accepts a properly-encoded ReceiveMsg from a cw20 contract
     Receive(Cw20ReceiveMsg),
 }
 
+/// The terms of a clawback created by a transfer
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TransferCreateMsg {
+    /// the receiver of tokens
+    pub holder: HumanAddr,
+    /// the duration of the clawback in seconds or blocks,
+    /// at least as long as the one of the outgoing clawback
+    pub clawback_period: Duration,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DisputeOutcome {
+    /// for the holder: the whole balance is sent to the holder and the clawback deleted
+    Holder {},
+    /// for the backup: the dispute is dismissed and the backup may act again
+    Backup {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Dispute {
+    /// the reason given by the holder
+    pub reason: String,
+    /// true once the arbiter dismissed the dispute
+    pub resolved: bool,
+}
+
+/// The actions the backup may take within the clawback period
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupAction {
+    /// same as HandleMsg::Refresh
+    Refresh {},
+    /// same as HandleMsg::Burn
+    Burn {},
+    /// same as HandleMsg::ClawbackTransfer from this clawback
+    Transfer {
+        to_id: String,
+        amount: Balance,
+        create: Option<TransferCreateMsg>,
+    },
+    /// same as HandleMsg::TransferRemote from this clawback
+    TransferRemote {
+        contract: HumanAddr,
+        to_id: String,
+        amount: Balance,
+    },
+    /// same as HandleMsg::Clawback
+    Clawback {
+        recipient: HumanAddr,
+        amount: Option<Balance>,
+    },
+    /// same as HandleMsg::UpdateHolder by the backup
+    UpdateHolder { holder: HumanAddr },
+    /// same as HandleMsg::UpdateBackup
+    UpdateBackup { backup: HumanAddr },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Multisig {
+    /// the keys that may propose and vote on backup actions
+    pub signers: Vec<HumanAddr>,
+    /// the number of votes needed to execute a backup action
+    pub threshold: u64,
+}
+
+// only deserialized once per message, so the size of Create does not matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ReceiveMsg {
     Create(CreateMsg),
     /// Adds all sent native tokens to the contract
+    /// (if the sender may deposit, see CreateMsg::depositors)
     TopUp {
         id: String,
     },
+    /// Adds the sent cw20 tokens from a TransferRemote to the clawback,
+    /// if the terms of the outgoing clawback match it
+    /// (the sender of the cw20 tokens must be one of the peers of this contract)
+    TransferIn {
+        id: String,
+        terms: TransferTerms,
+    },
+}
+
+/// The terms of the outgoing clawback of a TransferRemote
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TransferTerms {
+    pub backup: HumanAddr,
+    pub backup_multisig: Option<Multisig>,
+    pub clawback_period: Duration,
+    pub cw20_whitelist: Vec<HumanAddr>,
+    /// any native denom if not set
+    #[serde(default)]
+    pub native_whitelist: Option<Vec<String>>,
+    /// who made the transfer (the holder or the backup of the outgoing clawback)
+    #[serde(default)]
+    pub sender: Option<HumanAddr>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct CreateMsg {
     /// id is a human-readable name for the clawback to use later
-    /// 3-20 bytes of utf-8 text
-    pub id: String,
+    /// 3-20 characters of ascii letters, digits, '-' or '_'.
+    /// If not set, the contract assigns a new unique id (returned in CreateResponse)
+    pub id: Option<String>,
     /// the key that before "end_time" may transfer to Clawback
     /// (with the same "backup" and "clawback_period") or burn the tokens
     pub backup: HumanAddr,
@@ -67,13 +343,136 @@ pub struct CreateMsg {
     /// with the same "backup" and "clawback_period";
     /// after "end_time", they may transfer anywhere
     pub holder: HumanAddr,
-    /// the duration of the clawback
-    /// (end_time = block time + clawback_period)
-    pub clawback_period: u64,
+    /// the duration of the clawback in seconds or blocks
+    /// (end_time = block time / height + clawback_period)
+    pub clawback_period: Duration,
     /// Besides any possible tokens sent with the CreateMsg, this is a list of all cw20 token addresses
     /// that are accepted by the clawback during a top-up. This is required to avoid a DoS attack by topping-up
     /// with an invalid cw20 contract. See https://github.com/CosmWasm/cosmwasm-plus/issues/19
     pub cw20_whitelist: Option<Vec<HumanAddr>>,
+    /// if set, backup actions need the votes of these signers
+    /// instead of the "backup" key (see HandleMsg::Propose)
+    pub backup_multisig: Option<Multisig>,
+    /// limits on refreshing the clawback (none if not set)
+    pub refresh_policy: Option<RefreshPolicy>,
+    /// how the balance is released to the holder over the clawback period
+    /// (all at the end if not set)
+    pub release: Option<ReleaseSchedule>,
+    /// caps the tokens the backup may take back in total by burn, transfer or clawback
+    /// (tokens not listed here cannot be taken back at all); no cap if not set
+    pub max_clawback: Option<ClawbackLimit>,
+    /// the key that resolves disputes of the holder (no disputes if not set)
+    pub arbiter: Option<HumanAddr>,
+    /// who may top up the clawback (anyone if not set)
+    pub depositors: Option<DepositPolicy>,
+    /// the native denoms accepted by the clawback, on creation and top-up
+    /// (any if not set)
+    pub native_whitelist: Option<Vec<String>>,
+    /// what happens to sent native tokens not on the native whitelist
+    /// (rejected if not set)
+    pub unlisted_denoms: Option<UnlistedDenoms>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UnlistedDenoms {
+    /// the whole message fails
+    Reject {},
+    /// the unlisted tokens are sent back to the sender, the rest is accepted
+    Refund {},
+}
+
+impl Default for UnlistedDenoms {
+    fn default() -> Self {
+        UnlistedDenoms::Reject {}
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DepositPolicy {
+    /// anyone may top up
+    Anyone {},
+    /// only the holder and the backup (or its signers)
+    HolderAndBackup {},
+    /// only these addresses
+    Only { depositors: Vec<HumanAddr> },
+}
+
+impl Default for DepositPolicy {
+    fn default() -> Self {
+        DepositPolicy::Anyone {}
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClawbackLimit {
+    /// the maximum of each native token
+    pub native: Vec<Coin>,
+    /// the maximum of each cw20 token
+    pub cw20: Vec<Cw20CoinHuman>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReleaseSchedule {
+    /// all tokens at the end of the clawback period
+    Cliff {},
+    /// in proportion to the time / blocks passed in the clawback period
+    Linear {},
+    /// in this many equal parts over the clawback period
+    /// (at least 1, at most the length of the period in seconds / blocks)
+    Step { steps: u64 },
+}
+
+impl Default for ReleaseSchedule {
+    fn default() -> Self {
+        ReleaseSchedule::Cliff {}
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct RefreshPolicy {
+    /// how many times the clawback may be refreshed (by Refresh or an incoming transfer)
+    pub max_refreshes: Option<u64>,
+    /// the clawback cannot be refreshed to end later than this after its creation
+    /// (in the same units as the clawback period)
+    pub max_lock: Option<Duration>,
+    /// who may refresh with a Refresh message
+    pub refresh_by: RefreshBy,
+    /// whether the holder may refresh after the clawback period is over
+    pub holder_relock: bool,
+}
+
+impl Default for RefreshPolicy {
+    fn default() -> Self {
+        RefreshPolicy {
+            max_refreshes: None,
+            max_lock: None,
+            refresh_by: RefreshBy::HolderAndBackup,
+            holder_relock: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RefreshBy {
+    HolderAndBackup,
+    Holder,
+    Backup,
+    Nobody,
+}
+
+impl RefreshBy {
+    pub fn holder(&self) -> bool {
+        matches!(self, RefreshBy::HolderAndBackup | RefreshBy::Holder)
+    }
+
+    pub fn backup(&self) -> bool {
+        matches!(self, RefreshBy::HolderAndBackup | RefreshBy::Backup)
+    }
 }
 
 impl CreateMsg {
@@ -90,25 +489,117 @@ pub fn is_valid_name(name: &str) -> bool {
     if bytes.len() < 3 || bytes.len() > 20 {
         return false;
     }
-    true
+    bytes
+        .iter()
+        .all(|b| b.is_ascii_alphanumeric() || *b == b'-' || *b == b'_')
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
-    /// Show all open clawbacks. Return type is ListResponse.
-    List {},
+    /// Show open clawbacks, ordered by id. Return type is ListResponse.
+    List {
+        /// only list ids after this one (for pagination)
+        start_after: Option<String>,
+        /// how many ids to return (default 10, max 30)
+        limit: Option<u32>,
+    },
+    /// Show open clawbacks with their details, ordered by id.
+    /// Return type is ListDetailsResponse.
+    ListDetails {
+        /// only list clawbacks after this id (for pagination)
+        start_after: Option<String>,
+        /// how many clawbacks to return (default 10, max 30)
+        limit: Option<u32>,
+    },
+    /// Show open clawbacks of the holder, ordered by id. Return type is ListResponse.
+    ByHolder {
+        holder: HumanAddr,
+        /// only list ids after this one (for pagination)
+        start_after: Option<String>,
+        /// how many ids to return (default 10, max 30)
+        limit: Option<u32>,
+    },
+    /// Show open clawbacks of the backup, ordered by id. Return type is ListResponse.
+    ByBackup {
+        backup: HumanAddr,
+        /// only list ids after this one (for pagination)
+        start_after: Option<String>,
+        /// how many ids to return (default 10, max 30)
+        limit: Option<u32>,
+    },
+    /// Show open clawbacks that end between start and end (both inclusive,
+    /// both time or both height), ordered by the end time / height.
+    /// Return type is ExpiringResponse.
+    ExpiringBetween {
+        start: Expiration,
+        end: Expiration,
+        /// only list clawbacks after this one (for pagination)
+        start_after: Option<ExpiringClawback>,
+        /// how many clawbacks to return (default 10, max 30)
+        limit: Option<u32>,
+    },
+    /// Show clawbacks whose period is over, so the holder may withdraw them,
+    /// ordered by the end height and then the end time. Return type is ExpiringResponse.
+    Expired {
+        /// only list clawbacks after this one (for pagination)
+        start_after: Option<ExpiringClawback>,
+        /// how many clawbacks to return (default 10, max 30)
+        limit: Option<u32>,
+    },
     /// Returns the details of the named clawback, error if not created
     /// Return type: DetailsResponse.
     Details { id: String },
+    /// Returns the sum of balances of all open clawbacks.
+    /// Return type: TotalLockedResponse.
+    TotalLocked {},
+    /// Returns the given proposal, error if it passed or never existed.
+    /// Return type: ProposalResponse.
+    Proposal { proposal_id: u64 },
+    /// Show open proposals, ordered by id. Return type is ListProposalsResponse.
+    ListProposals {
+        /// only list proposals after this id (for pagination)
+        start_after: Option<u64>,
+        /// how many proposals to return (default 10, max 30)
+        limit: Option<u32>,
+    },
+    /// Returns the contract-wide settings. Return type: ConfigResponse.
+    Config {},
+}
+
+/// Returned as data from Create
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct CreateResponse {
+    /// id of the created clawback
+    pub id: String,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct ListResponse {
-    /// list all registered ids
+    /// list the registered ids
     pub clawbacks: Vec<String>,
 }
 
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ExpiringResponse {
+    /// the listed clawbacks with their end time / height
+    pub clawbacks: Vec<ExpiringClawback>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ExpiringClawback {
+    /// id of the clawback
+    pub id: String,
+    /// end time / height of the clawback
+    pub end_time: Expiration,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ListDetailsResponse {
+    /// details of the listed clawbacks
+    pub clawbacks: Vec<DetailsResponse>,
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct DetailsResponse {
     /// id of this clawback
@@ -120,16 +611,96 @@ pub struct DetailsResponse {
     /// with the same "backup" and "clawback_period";
     /// after "end_time", they may transfer anywhere
     pub holder: HumanAddr,
-    /// end time (in seconds since epoch 00:00:00 UTC on 1 January 1970);
-    /// when block time exceeds this value, the holder can transfer outside Clawback.
-    pub end_time: u64,
-    /// the duration of the clawback
-    /// (end_time = block time + clawback_period)
-    pub clawback_period: u64,
+    /// end time (in seconds since epoch 00:00:00 UTC on 1 January 1970) or end block height;
+    /// when block time / height exceeds this value, the holder can transfer outside Clawback.
+    pub end_time: Expiration,
+    /// the duration of the clawback in seconds or blocks
+    /// (end_time = block time / height + clawback_period)
+    pub clawback_period: Duration,
     /// Balance in native tokens
     pub native_balance: Vec<Coin>,
     /// Balance in cw20 tokens
     pub cw20_balance: Vec<Cw20CoinHuman>,
     /// Whitelisted cw20 tokens
     pub cw20_whitelist: Vec<HumanAddr>,
+    /// the signers that take the backup actions, if any
+    pub backup_multisig: Option<Multisig>,
+    /// the key proposed to replace the backup, if any
+    pub pending_backup: Option<HumanAddr>,
+    /// limits on refreshing the clawback
+    pub refresh_policy: RefreshPolicy,
+    /// the latest end time / height a refresh may set (from "max_lock")
+    pub max_end_time: Option<Expiration>,
+    /// how many times the clawback was refreshed
+    pub refreshes: u64,
+    /// how the balance is released to the holder over the clawback period
+    pub release: ReleaseSchedule,
+    /// native tokens the holder may withdraw now
+    pub vested_native: Vec<Coin>,
+    /// cw20 tokens the holder may withdraw now
+    pub vested_cw20: Vec<Cw20CoinHuman>,
+    /// native tokens still only for the backup to take
+    pub unvested_native: Vec<Coin>,
+    /// cw20 tokens still only for the backup to take
+    pub unvested_cw20: Vec<Cw20CoinHuman>,
+    /// the cap on the tokens the backup may take back, if any
+    pub max_clawback: Option<ClawbackLimit>,
+    /// native tokens the backup took back so far
+    pub clawed_back_native: Vec<Coin>,
+    /// cw20 tokens the backup took back so far
+    pub clawed_back_cw20: Vec<Cw20CoinHuman>,
+    /// the key that resolves disputes of the holder, if any
+    pub arbiter: Option<HumanAddr>,
+    /// the dispute of the holder, if any
+    pub dispute: Option<Dispute>,
+    /// who may top up the clawback
+    pub depositors: DepositPolicy,
+    /// Whitelisted native denoms, if restricted
+    pub native_whitelist: Option<Vec<String>>,
+    /// what happens to sent native tokens not on the native whitelist
+    pub unlisted_denoms: UnlistedDenoms,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ProposalResponse {
+    /// id of this proposal
+    pub id: u64,
+    /// the clawback the action is taken on
+    pub clawback_id: String,
+    pub action: BackupAction,
+    /// the signers that voted for the action so far
+    pub votes: Vec<HumanAddr>,
+    /// the proposal cannot pass after this time / height
+    pub expires: Expiration,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ListProposalsResponse {
+    pub proposals: Vec<ProposalResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ConfigResponse {
+    /// the address that may pause the contract, if any
+    pub admin: Option<HumanAddr>,
+    /// the address that receives native tokens of burned clawbacks, if any
+    pub native_sink: Option<HumanAddr>,
+    /// true while the contract is paused
+    pub paused: bool,
+    /// what is still allowed while the contract is paused
+    pub emergency_policy: EmergencyPolicy,
+    /// the only tokens clawbacks accept, if restricted
+    pub allowlist: Option<TokenAllowlist>,
+    /// the most different tokens a clawback may hold, if limited
+    pub max_tokens: Option<u32>,
+    /// the clawback contracts that may TransferIn to this one
+    pub peers: Vec<HumanAddr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TotalLockedResponse {
+    /// Native tokens locked in all clawbacks
+    pub native: Vec<Coin>,
+    /// Cw20 tokens locked in all clawbacks
+    pub cw20: Vec<Cw20CoinHuman>,
 }
@@ -2,12 +2,22 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_std::{
-    Api, CanonicalAddr, Coin, Env, HumanAddr, Order, ReadonlyStorage, StdError, StdResult, Storage,
+    Api, CanonicalAddr, Coin, Empty, Env, HumanAddr, Order, ReadonlyStorage, StdError, StdResult,
+    Storage, Uint128,
+};
+use cosmwasm_storage::{
+    bucket, bucket_read, prefixed_read, singleton, singleton_read, Bucket, ReadonlyBucket,
+    ReadonlySingleton, Singleton,
 };
-use cosmwasm_storage::{bucket, bucket_read, prefixed_read, Bucket, ReadonlyBucket};
 
+use cw0::{calc_range_start_string, Duration, Expiration};
 use cw20::{Balance, Cw20Coin};
 
+use crate::msg::{
+    BackupAction, Dispute, EmergencyPolicy, RefreshBy, RefreshPolicy, ReleaseSchedule,
+    UnlistedDenoms,
+};
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
 pub struct GenericBalance {
     pub native: Vec<Coin>,
@@ -15,14 +25,15 @@ pub struct GenericBalance {
 }
 
 impl GenericBalance {
+    #[allow(clippy::result_unit_err)]
     pub fn remove_tokens(&mut self, remove: Balance) -> Result<(), ()> {
         match remove {
             Balance::Native(balance) => {
-                let mut indexed_amount = Vec::with_capacity(balance.0.len());
-                // check balances
+                // work on a copy, so that nothing is removed if any token is missing
+                // (and the same denom listed twice is subtracted twice)
+                let mut native = self.native.clone();
                 for token in balance.0 {
-                    let index = self
-                        .native
+                    let index = native
                         .iter()
                         .enumerate()
                         .find_map(|(i, exist)| {
@@ -33,18 +44,16 @@ impl GenericBalance {
                             }
                         })
                         .ok_or(())?;
-                    if token.amount > self.native[index].amount {
+                    if token.amount > native[index].amount {
                         return Err(());
                     } else {
-                        let new_amount =
-                            (self.native[index].amount - token.amount).map_err(|_| ())?;
-                        indexed_amount.push((index, new_amount));
+                        native[index].amount =
+                            (native[index].amount - token.amount).map_err(|_| ())?;
                     }
                 }
-                // update balances
-                for (index, new_amount) in indexed_amount.iter() {
-                    self.native[*index].amount = *new_amount;
-                }
+                // update balances, dropping the emptied tokens
+                native.retain(|c| !c.amount.is_zero());
+                self.native = native;
                 Ok(())
             }
             Balance::Cw20(token) => {
@@ -64,7 +73,11 @@ impl GenericBalance {
                     Err(())
                 } else {
                     let new_amount = (self.cw20[index].amount - token.amount).map_err(|_| ())?;
-                    self.cw20[index].amount = new_amount;
+                    if new_amount.is_zero() {
+                        self.cw20.remove(index);
+                    } else {
+                        self.cw20[index].amount = new_amount;
+                    }
                     Ok(())
                 }
             }
@@ -103,9 +116,160 @@ impl GenericBalance {
             }
         };
     }
+
+    /// returns the number of different tokens held
+    pub fn token_count(&self) -> usize {
+        self.native.iter().filter(|c| !c.amount.is_zero()).count()
+            + self.cw20.iter().filter(|c| !c.amount.is_zero()).count()
+    }
+
+    /// returns true if there are no tokens left
+    pub fn is_empty(&self) -> bool {
+        self.native.iter().all(|c| c.amount.is_zero())
+            && self.cw20.iter().all(|c| c.amount.is_zero())
+    }
+
+    /// returns the part of this balance that is released when the given fraction
+    /// of the tokens (this balance, the already withdrawn ones and the unreleased ones
+    /// the backup took back) is released
+    pub fn withdrawable(
+        &self,
+        withdrawn: &GenericBalance,
+        taken_back: &GenericBalance,
+        numerator: u128,
+        denominator: u128,
+    ) -> GenericBalance {
+        let released = |amount: Uint128, withdrawn: Uint128, taken_back: Uint128| -> Uint128 {
+            let total = amount
+                .u128()
+                .saturating_add(withdrawn.u128())
+                .saturating_add(taken_back.u128());
+            let vested = if numerator >= denominator {
+                total
+            } else {
+                // split so that the product cannot overflow
+                total / denominator * numerator + total % denominator * numerator / denominator
+            };
+            Uint128(vested.saturating_sub(withdrawn.u128()).min(amount.u128()))
+        };
+        let native_amount = |balance: &GenericBalance, denom: &str| {
+            balance
+                .native
+                .iter()
+                .find(|w| w.denom == denom)
+                .map(|w| w.amount)
+                .unwrap_or_default()
+        };
+        let cw20_amount = |balance: &GenericBalance, address: &CanonicalAddr| {
+            balance
+                .cw20
+                .iter()
+                .find(|w| &w.address == address)
+                .map(|w| w.amount)
+                .unwrap_or_default()
+        };
+        let native = self
+            .native
+            .iter()
+            .map(|c| Coin {
+                denom: c.denom.clone(),
+                amount: released(
+                    c.amount,
+                    native_amount(withdrawn, &c.denom),
+                    native_amount(taken_back, &c.denom),
+                ),
+            })
+            .filter(|c| !c.amount.is_zero())
+            .collect();
+        let cw20 = self
+            .cw20
+            .iter()
+            .map(|c| Cw20Coin {
+                address: c.address.clone(),
+                amount: released(
+                    c.amount,
+                    cw20_amount(withdrawn, &c.address),
+                    cw20_amount(taken_back, &c.address),
+                ),
+            })
+            .filter(|c| !c.amount.is_zero())
+            .collect();
+        GenericBalance { native, cw20 }
+    }
+
+    /// removes the tokens of the other balance as far as they are present
+    /// and returns what was removed
+    pub fn remove_up_to(&mut self, other: &GenericBalance) -> GenericBalance {
+        let mut removed = GenericBalance::default();
+        for coin in self.native.iter_mut() {
+            if let Some(remove) = other.native.iter().find(|o| o.denom == coin.denom) {
+                let amount = coin.amount.u128().min(remove.amount.u128());
+                coin.amount = Uint128(coin.amount.u128() - amount);
+                removed.native.push(Coin {
+                    denom: coin.denom.clone(),
+                    amount: Uint128(amount),
+                });
+            }
+        }
+        for token in self.cw20.iter_mut() {
+            if let Some(remove) = other.cw20.iter().find(|o| o.address == token.address) {
+                let amount = token.amount.u128().min(remove.amount.u128());
+                token.amount = Uint128(token.amount.u128() - amount);
+                removed.cw20.push(Cw20Coin {
+                    address: token.address.clone(),
+                    amount: Uint128(amount),
+                });
+            }
+        }
+        self.native.retain(|c| !c.amount.is_zero());
+        self.cw20.retain(|c| !c.amount.is_zero());
+        removed.native.retain(|c| !c.amount.is_zero());
+        removed.cw20.retain(|c| !c.amount.is_zero());
+        removed
+    }
+
+    /// adds all tokens of the other balance
+    pub fn add_balance(&mut self, other: &GenericBalance) {
+        self.add_tokens(Balance::from(other.native.clone()));
+        for token in other.cw20.iter() {
+            self.add_tokens(Balance::Cw20(token.clone()));
+        }
+    }
+
+    /// removes all tokens of the other balance
+    /// (fails if any of them is not present in a sufficient amount)
+    #[allow(clippy::result_unit_err)]
+    pub fn remove_balance(&mut self, other: &GenericBalance) -> Result<(), ()> {
+        let native: Vec<_> = other
+            .native
+            .iter()
+            .filter(|c| !c.amount.is_zero())
+            .cloned()
+            .collect();
+        self.remove_tokens(Balance::from(native))?;
+        for token in other.cw20.iter().filter(|c| !c.amount.is_zero()) {
+            self.remove_tokens(Balance::Cw20(token.clone()))?;
+        }
+        Ok(())
+    }
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+impl From<Balance> for GenericBalance {
+    fn from(balance: Balance) -> GenericBalance {
+        match balance {
+            Balance::Native(balance) => GenericBalance {
+                native: balance.0,
+                cw20: vec![],
+            },
+            Balance::Cw20(token) => GenericBalance {
+                native: vec![],
+                cw20: vec![token],
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct Clawback {
     /// the key that before "end_time" may transfer to Clawback
     /// (with the same "backup" and "clawback_period") or burn the tokens
@@ -114,21 +278,296 @@ pub struct Clawback {
     /// with the same "backup" and "clawback_period";
     /// after "end_time", they may transfer anywhere
     pub holder: CanonicalAddr,
-    /// end time (in seconds since epoch 00:00:00 UTC on 1 January 1970);
-    /// when block time exceeds this value, the holder can transfer outside Clawback.
-    pub end_time: u64,
-    /// the duration of the clawback
-    /// (end_time = block time + clawback_period)
-    pub clawback_period: u64,
+    /// end time (in seconds since epoch 00:00:00 UTC on 1 January 1970) or end block height;
+    /// when block time / height exceeds this value, the holder can transfer outside Clawback.
+    pub end_time: Expiration,
+    /// the duration of the clawback in seconds or blocks
+    /// (end_time = block time / height + clawback_period)
+    pub clawback_period: Duration,
     /// Balance in Native and Cw20 tokens
     pub balance: GenericBalance,
     /// All possible contracts that we accept tokens from
     pub cw20_whitelist: Vec<CanonicalAddr>,
+    /// if set, the backup actions need the votes of this set of keys
+    /// (see `Proposal`) and the "backup" key cannot act alone
+    #[serde(default)]
+    pub backup_multisig: Option<BackupMultisig>,
+    /// the key proposed by the backup to replace it, until it accepts
+    #[serde(default)]
+    pub pending_backup: Option<CanonicalAddr>,
+    /// limits on refreshing the clawback
+    #[serde(default)]
+    pub refresh_policy: RefreshPolicy,
+    /// the latest end time / height a refresh may set (from the policy's "max_lock")
+    #[serde(default)]
+    pub max_end_time: Option<Expiration>,
+    /// how many times the clawback was refreshed
+    #[serde(default)]
+    pub refreshes: u64,
+    /// how the balance is released to the holder over the clawback period
+    #[serde(default)]
+    pub release: ReleaseSchedule,
+    /// the tokens the holder withdrew before the end of the current period
+    #[serde(default)]
+    pub withdrawn: GenericBalance,
+    /// the unreleased tokens the backup took back in the current period
+    #[serde(default)]
+    pub taken_back: GenericBalance,
+    /// the part of the balance released in earlier periods, which the holder
+    /// may withdraw at any time
+    #[serde(default)]
+    pub released: GenericBalance,
+    /// the most tokens the backup may take back in total (no cap if not set)
+    #[serde(default)]
+    pub max_clawback: Option<GenericBalance>,
+    /// the tokens the backup took back so far
+    #[serde(default)]
+    pub clawed_back: GenericBalance,
+    /// the key that resolves disputes of the holder
+    #[serde(default)]
+    pub arbiter: Option<CanonicalAddr>,
+    /// the dispute of the holder, if any (only one per clawback)
+    #[serde(default)]
+    pub dispute: Option<Dispute>,
+    /// who may top up the clawback
+    #[serde(default)]
+    pub depositors: Depositors,
+    /// the native denoms we accept (any if not set)
+    #[serde(default)]
+    pub native_whitelist: Option<Vec<String>>,
+    /// what happens to sent native tokens not on the native whitelist
+    #[serde(default)]
+    pub unlisted_denoms: UnlistedDenoms,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Depositors {
+    Anyone {},
+    HolderAndBackup {},
+    Only { depositors: Vec<CanonicalAddr> },
+}
+
+impl Default for Depositors {
+    fn default() -> Self {
+        Depositors::Anyone {}
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct BackupMultisig {
+    /// the keys that may propose and vote on backup actions (sorted)
+    pub signers: Vec<CanonicalAddr>,
+    /// the number of votes needed to execute a backup action
+    pub threshold: u64,
+}
+
+/// returns true once the block time / height is past the given end
+/// (so the end time / height itself is still within the period)
+fn is_past(end: &Expiration, env: &Env) -> bool {
+    match end {
+        Expiration::AtTime(time) => env.block.time > *time,
+        Expiration::AtHeight(height) => env.block.height > *height,
+        Expiration::Never {} => false,
+    }
 }
 
 impl Clawback {
     pub fn is_expired(&self, env: &Env) -> bool {
-        env.block.time > self.end_time
+        is_past(&self.end_time, env)
+    }
+
+    /// returns true if the sender may act as the backup on its own
+    /// (i.e. it is the backup key and there is no multisig)
+    pub fn is_sole_backup(&self, sender: &CanonicalAddr) -> bool {
+        self.backup_multisig.is_none() && sender == &self.backup
+    }
+
+    /// returns true if the other clawback has the same backup authority
+    pub fn same_backup(&self, other: &Clawback) -> bool {
+        self.backup == other.backup && self.backup_multisig == other.backup_multisig
+    }
+
+    /// returns true if the sender may top up the clawback
+    pub fn may_deposit(&self, sender: &CanonicalAddr) -> bool {
+        match &self.depositors {
+            Depositors::Anyone {} => true,
+            Depositors::HolderAndBackup {} => {
+                sender == &self.holder
+                    || sender == &self.backup
+                    || matches!(&self.backup_multisig, Some(m) if m.signers.contains(sender))
+            }
+            Depositors::Only { depositors } => depositors.contains(sender),
+        }
+    }
+
+    /// returns true if a transfer by the sender into this clawback may refresh it:
+    /// with the default refresh policy any transfer may, otherwise only one by
+    /// a party the policy allows to refresh
+    pub fn may_refresh_by_transfer(&self, sender: Option<&CanonicalAddr>) -> bool {
+        match self.refresh_policy.refresh_by {
+            RefreshBy::HolderAndBackup => true,
+            RefreshBy::Holder => sender == Some(&self.holder),
+            RefreshBy::Backup => sender == Some(&self.backup),
+            RefreshBy::Nobody => false,
+        }
+    }
+
+    /// returns true while a dispute of the holder is not resolved
+    pub fn is_disputed(&self) -> bool {
+        matches!(&self.dispute, Some(dispute) if !dispute.resolved)
+    }
+
+    /// restarts the clawback period from the current block
+    /// (and so the release of the rest of the balance; what is released stays so)
+    pub fn refresh(&mut self, env: &Env) {
+        let released = self.withdrawable(env);
+        self.relock(env);
+        self.released = released;
+    }
+
+    /// restarts the clawback period from the current block
+    /// and the release of the whole balance (for the holder locking it again)
+    pub fn relock(&mut self, env: &Env) {
+        self.end_time = self.clawback_period.after(&env.block);
+        self.refreshes += 1;
+        self.withdrawn = GenericBalance::default();
+        self.taken_back = GenericBalance::default();
+        self.released = GenericBalance::default();
+    }
+
+    /// returns the released fraction of the balance at this block (as numerator, denominator)
+    pub fn released_fraction(&self, env: &Env) -> (u128, u128) {
+        if self.is_expired(env) {
+            return (1, 1);
+        }
+        let (now, end, period) = match (self.end_time, self.clawback_period) {
+            (Expiration::AtTime(end), Duration::Time(period)) => (env.block.time, end, period),
+            (Expiration::AtHeight(end), Duration::Height(period)) => {
+                (env.block.height, end, period)
+            }
+            _ => return (0, 1),
+        };
+        if period == 0 {
+            return (0, 1);
+        }
+        let elapsed = now.saturating_sub(end.saturating_sub(period)).min(period) as u128;
+        let period = period as u128;
+        match self.release {
+            ReleaseSchedule::Cliff {} => (0, 1),
+            ReleaseSchedule::Linear {} => (elapsed, period),
+            ReleaseSchedule::Step { steps } if steps > 0 => {
+                (elapsed * steps as u128 / period, steps as u128)
+            }
+            ReleaseSchedule::Step { .. } => (0, 1),
+        }
+    }
+
+    /// returns the part of the balance the holder may withdraw at this block
+    pub fn withdrawable(&self, env: &Env) -> GenericBalance {
+        let (numerator, denominator) = self.released_fraction(env);
+        // the tokens released in earlier periods are not part of the current release
+        let mut scheduled = self.balance.clone();
+        let released = scheduled.remove_up_to(&self.released);
+        let mut withdrawable =
+            scheduled.withdrawable(&self.withdrawn, &self.taken_back, numerator, denominator);
+        withdrawable.add_balance(&released);
+        withdrawable
+    }
+
+    /// records tokens the holder withdrew before the end of the period,
+    /// out of the tokens released in earlier periods first
+    pub fn record_withdrawal(&mut self, amount: &GenericBalance) {
+        let mut rest = amount.clone();
+        let released = self.released.remove_up_to(amount);
+        let _ = rest.remove_balance(&released);
+        self.withdrawn.add_balance(&rest);
+    }
+
+    /// returns the part of the balance the backup may take back at this block
+    /// (all but what is released to the holder)
+    pub fn unreleased(&self, env: &Env) -> GenericBalance {
+        let mut unreleased = self.balance.clone();
+        // the released part never exceeds the balance
+        let _ = unreleased.remove_balance(&self.withdrawable(env));
+        unreleased
+    }
+
+    /// records tokens taken back by the backup
+    /// (fails if this takes more than max_clawback allows)
+    #[allow(clippy::result_unit_err)]
+    pub fn record_clawback(&mut self, amount: &GenericBalance) -> Result<(), ()> {
+        let mut clawed_back = self.clawed_back.clone();
+        clawed_back.add_balance(amount);
+        if let Some(max) = &self.max_clawback {
+            max.clone().remove_balance(&clawed_back)?;
+        }
+        self.clawed_back = clawed_back;
+        Ok(())
+    }
+
+    /// returns true if the refresh policy lets the holder refresh at this block
+    /// (not taking into account the limits checked by `can_refresh`)
+    pub fn holder_may_refresh(&self, env: &Env) -> bool {
+        self.refresh_policy.refresh_by.holder()
+            && (self.refresh_policy.holder_relock || !self.is_expired(env))
+    }
+
+    /// returns true if the refresh policy allows another refresh at this block
+    /// (not taking into account who refreshes)
+    pub fn can_refresh(&self, env: &Env) -> bool {
+        if let Some(max) = self.refresh_policy.max_refreshes {
+            if self.refreshes >= max {
+                return false;
+            }
+        }
+        match (self.max_end_time, self.clawback_period.after(&env.block)) {
+            (None, _) => true,
+            (Some(Expiration::AtTime(max)), Expiration::AtTime(end)) => end <= max,
+            (Some(Expiration::AtHeight(max)), Expiration::AtHeight(end)) => end <= max,
+            _ => false,
+        }
+    }
+
+    /// returns true if this clawback's period is at least as long as the given one
+    /// (and both are measured in the same units)
+    pub fn covers_period(&self, period: &Duration) -> bool {
+        match (self.clawback_period, period) {
+            (Duration::Time(own), Duration::Time(other)) => own >= *other,
+            (Duration::Height(own), Duration::Height(other)) => own >= *other,
+            _ => false,
+        }
+    }
+
+    /// returns true if this clawback accepts all tokens of the balance
+    pub fn accepts(&self, balance: &Balance) -> bool {
+        match balance {
+            Balance::Native(balance) => balance.0.iter().all(|c| self.accepts_native(&c.denom)),
+            Balance::Cw20(token) => self.cw20_whitelist.contains(&token.address),
+        }
+    }
+
+    pub fn accepts_native(&self, denom: &str) -> bool {
+        match &self.native_whitelist {
+            Some(whitelist) => whitelist.iter().any(|d| d == denom),
+            None => true,
+        }
+    }
+
+    /// returns true if this clawback accepts every token the given whitelists allow
+    pub fn covers_whitelists(
+        &self,
+        cw20_whitelist: &[CanonicalAddr],
+        native_whitelist: &Option<Vec<String>>,
+    ) -> bool {
+        let native = match native_whitelist {
+            Some(denoms) => denoms.iter().all(|d| self.accepts_native(d)),
+            None => self.native_whitelist.is_none(),
+        };
+        native
+            && cw20_whitelist
+                .iter()
+                .all(|address| self.cw20_whitelist.contains(address))
     }
 
     pub fn human_whitelist<A: Api>(&self, api: &A) -> StdResult<Vec<HumanAddr>> {
@@ -139,16 +578,494 @@ impl Clawback {
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct Config {
+    /// the address that receives native tokens of burned clawbacks
+    /// (e.g. a community pool or an unspendable address),
+    /// as a contract cannot burn native tokens itself
+    pub native_sink: Option<CanonicalAddr>,
+    /// the address that may pause the contract
+    #[serde(default)]
+    pub admin: Option<CanonicalAddr>,
+    /// true while the contract is paused
+    #[serde(default)]
+    pub paused: bool,
+    /// what is still allowed while the contract is paused
+    #[serde(default)]
+    pub emergency_policy: EmergencyPolicy,
+    /// the only tokens clawbacks accept (any if not set)
+    #[serde(default)]
+    pub allowlist: Option<Allowlist>,
+    /// the most different tokens a clawback may hold
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    /// the clawback contracts that may transfer tokens in
+    #[serde(default)]
+    pub peers: Vec<CanonicalAddr>,
+}
+
+impl Config {
+    /// returns true if the allowlist (if any) contains all tokens of the balance
+    pub fn allows(&self, balance: &Balance) -> bool {
+        match balance {
+            Balance::Native(balance) => balance.0.iter().all(|c| self.allows_native(&c.denom)),
+            Balance::Cw20(token) => self.allows_cw20(&token.address),
+        }
+    }
+
+    pub fn allows_native(&self, denom: &str) -> bool {
+        match &self.allowlist {
+            Some(allowlist) => allowlist.native.iter().any(|d| d == denom),
+            None => true,
+        }
+    }
+
+    pub fn allows_cw20(&self, address: &CanonicalAddr) -> bool {
+        match &self.allowlist {
+            Some(allowlist) => allowlist.cw20.contains(address),
+            None => true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct Allowlist {
+    /// the native denoms clawbacks accept
+    pub native: Vec<String>,
+    /// the cw20 token contracts clawbacks accept
+    pub cw20: Vec<CanonicalAddr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Proposal {
+    /// the clawback the action is taken on
+    pub clawback_id: String,
+    pub action: BackupAction,
+    /// the signers that voted for the action so far
+    pub votes: Vec<CanonicalAddr>,
+    /// the end of the clawback period when proposed, the proposal cannot pass after that
+    pub expires: Expiration,
+}
+
+impl Proposal {
+    pub fn is_expired(&self, env: &Env) -> bool {
+        is_past(&self.expires, env)
+    }
+}
+
+pub const KEY_CONFIG: &[u8] = b"config";
+
+pub fn config<S: Storage>(storage: &mut S) -> Singleton<'_, S, Config> {
+    singleton(storage, KEY_CONFIG)
+}
+
+pub fn config_read<S: ReadonlyStorage>(storage: &S) -> ReadonlySingleton<'_, S, Config> {
+    singleton_read(storage, KEY_CONFIG)
+}
+
+pub const KEY_TOTAL_LOCKED: &[u8] = b"total_locked";
+
+/// The sum of balances of all clawbacks, i.e. the funds held by the contract
+/// that belong to some clawback
+pub fn total_locked<S: Storage>(storage: &mut S) -> Singleton<'_, S, GenericBalance> {
+    singleton(storage, KEY_TOTAL_LOCKED)
+}
+
+pub fn total_locked_read<S: ReadonlyStorage>(
+    storage: &S,
+) -> ReadonlySingleton<'_, S, GenericBalance> {
+    singleton_read(storage, KEY_TOTAL_LOCKED)
+}
+
+pub const KEY_MIGRATION: &[u8] = b"migration";
+
+/// The key of the last clawback converted by an unfinished migration from 0.3.x
+/// (empty before the first one); not set once all clawbacks are converted
+pub fn migration_cursor<S: Storage>(storage: &mut S) -> Singleton<'_, S, Vec<u8>> {
+    singleton(storage, KEY_MIGRATION)
+}
+
+pub fn migration_cursor_read<S: ReadonlyStorage>(storage: &S) -> ReadonlySingleton<'_, S, Vec<u8>> {
+    singleton_read(storage, KEY_MIGRATION)
+}
+
+pub const KEY_ID_COUNTER: &[u8] = b"id_counter";
+
+/// The number of the last id assigned by the contract
+pub fn id_counter<S: Storage>(storage: &mut S) -> Singleton<'_, S, u64> {
+    singleton(storage, KEY_ID_COUNTER)
+}
+
+pub fn id_counter_read<S: ReadonlyStorage>(storage: &S) -> ReadonlySingleton<'_, S, u64> {
+    singleton_read(storage, KEY_ID_COUNTER)
+}
+
 pub const PREFIX_CLAWBACK: &[u8] = b"clawback";
 
-pub fn clawbacks<S: Storage>(storage: &mut S) -> Bucket<S, Clawback> {
+pub const KEY_PROPOSAL_COUNTER: &[u8] = b"proposal_counter";
+pub const PREFIX_PROPOSAL: &[u8] = b"proposal";
+
+pub fn proposals<S: Storage>(storage: &mut S) -> Bucket<'_, S, Proposal> {
+    bucket(storage, PREFIX_PROPOSAL)
+}
+
+pub fn proposals_read<S: ReadonlyStorage>(storage: &S) -> ReadonlyBucket<'_, S, Proposal> {
+    bucket_read(storage, PREFIX_PROPOSAL)
+}
+
+pub const PREFIX_PROPOSAL_INDEX: &[u8] = b"proposal_index";
+
+/// Index of open proposal ids by the clawback they act on
+pub fn proposal_index<'a, S: Storage>(
+    storage: &'a mut S,
+    clawback_id: &[u8],
+) -> Bucket<'a, S, Empty> {
+    Bucket::multilevel(storage, &[PREFIX_PROPOSAL_INDEX, clawback_id])
+}
+
+pub fn proposal_index_read<'a, S: ReadonlyStorage>(
+    storage: &'a S,
+    clawback_id: &[u8],
+) -> ReadonlyBucket<'a, S, Empty> {
+    ReadonlyBucket::multilevel(storage, &[PREFIX_PROPOSAL_INDEX, clawback_id])
+}
+
+/// This returns the ids of all open proposals on the given clawback
+pub fn clawback_proposal_ids<S: ReadonlyStorage>(
+    storage: &S,
+    clawback_id: &[u8],
+) -> StdResult<Vec<u64>> {
+    proposal_index_read(storage, clawback_id)
+        .range(None, None, Order::Ascending)
+        .map(|item| {
+            let (k, _) = item?;
+            let mut id = [0u8; 8];
+            id.copy_from_slice(&k);
+            Ok(u64::from_be_bytes(id))
+        })
+        .collect()
+}
+
+/// This saves the proposal under the given id and indexes it by its clawback.
+/// All handlers should use this instead of saving into `proposals` directly.
+pub fn save_proposal<S: Storage>(
+    storage: &mut S,
+    proposal_id: u64,
+    proposal: &Proposal,
+) -> StdResult<()> {
+    proposal_index(storage, proposal.clawback_id.as_bytes())
+        .save(&proposal_id.to_be_bytes(), &Empty {})?;
+    proposals(storage).save(&proposal_id.to_be_bytes(), proposal)
+}
+
+/// This removes the proposal under the given id on the given clawback from storage and the index
+pub fn remove_proposal<S: Storage>(storage: &mut S, proposal_id: u64, clawback_id: &[u8]) {
+    proposal_index(storage, clawback_id).remove(&proposal_id.to_be_bytes());
+    proposals(storage).remove(&proposal_id.to_be_bytes());
+}
+
+/// The id of the last proposal
+pub fn proposal_counter<S: Storage>(storage: &mut S) -> Singleton<'_, S, u64> {
+    singleton(storage, KEY_PROPOSAL_COUNTER)
+}
+
+pub fn proposal_counter_read<S: ReadonlyStorage>(storage: &S) -> ReadonlySingleton<'_, S, u64> {
+    singleton_read(storage, KEY_PROPOSAL_COUNTER)
+}
+
+/// This assigns the id for a new proposal
+pub fn next_proposal_id<S: Storage>(storage: &mut S) -> StdResult<u64> {
+    let id = proposal_counter_read(storage)
+        .may_load()?
+        .unwrap_or_default()
+        + 1;
+    proposal_counter(storage).save(&id)?;
+    Ok(id)
+}
+
+/// This returns up to limit open proposals after start_after, ordered by id
+pub fn range_proposals<S: ReadonlyStorage>(
+    storage: &S,
+    start_after: Option<u64>,
+    limit: usize,
+) -> StdResult<Vec<(u64, Proposal)>> {
+    // the key right after start_after
+    let start = start_after.map(|id| {
+        let mut key = id.to_be_bytes().to_vec();
+        key.push(0);
+        key
+    });
+    proposals_read(storage)
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (k, proposal) = item?;
+            let mut id = [0u8; 8];
+            id.copy_from_slice(&k);
+            Ok((u64::from_be_bytes(id), proposal))
+        })
+        .collect()
+}
+
+pub fn clawbacks<S: Storage>(storage: &mut S) -> Bucket<'_, S, Clawback> {
     bucket(storage, PREFIX_CLAWBACK)
 }
 
-pub fn clawbacks_read<S: ReadonlyStorage>(storage: &S) -> ReadonlyBucket<S, Clawback> {
+pub fn clawbacks_read<S: ReadonlyStorage>(storage: &S) -> ReadonlyBucket<'_, S, Clawback> {
     bucket_read(storage, PREFIX_CLAWBACK)
 }
 
+pub const PREFIX_HOLDER_INDEX: &[u8] = b"holder_index";
+pub const PREFIX_BACKUP_INDEX: &[u8] = b"backup_index";
+
+/// Index of clawback ids by their holder (or backup, depending on the prefix)
+pub fn address_index<'a, S: Storage>(
+    storage: &'a mut S,
+    prefix: &[u8],
+    address: &CanonicalAddr,
+) -> Bucket<'a, S, Empty> {
+    Bucket::multilevel(storage, &[prefix, address.as_slice()])
+}
+
+pub fn address_index_read<'a, S: ReadonlyStorage>(
+    storage: &'a S,
+    prefix: &[u8],
+    address: &CanonicalAddr,
+) -> ReadonlyBucket<'a, S, Empty> {
+    ReadonlyBucket::multilevel(storage, &[prefix, address.as_slice()])
+}
+
+/// This returns up to limit ids of clawbacks after start_after in the given address index
+pub fn address_index_ids<S: ReadonlyStorage>(
+    storage: &S,
+    prefix: &[u8],
+    address: &CanonicalAddr,
+    start_after: Option<String>,
+    limit: usize,
+) -> StdResult<Vec<String>> {
+    let start = calc_range_start_string(start_after);
+    address_index_read(storage, prefix, address)
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (k, _) = item?;
+            String::from_utf8(k).map_err(|_| StdError::invalid_utf8("parsing clawback key"))
+        })
+        .collect()
+}
+
+pub const PREFIX_EXPIRY_INDEX: &[u8] = b"expiry_index";
+
+/// Index of clawback ids ordered by their end time / height;
+/// keys are created with `expiry_key`
+pub fn expiry_index<S: Storage>(storage: &mut S) -> Bucket<'_, S, Empty> {
+    bucket(storage, PREFIX_EXPIRY_INDEX)
+}
+
+pub fn expiry_index_read<S: ReadonlyStorage>(storage: &S) -> ReadonlyBucket<'_, S, Empty> {
+    bucket_read(storage, PREFIX_EXPIRY_INDEX)
+}
+
+/// This returns the prefix of all expiry index keys at the given end time / height:
+/// b"h" or b"t" followed by the big-endian value, so that keys sort by expiration
+/// (None for expirations that never happen)
+pub fn expiry_prefix(end_time: &Expiration) -> Option<Vec<u8>> {
+    let (kind, value) = match end_time {
+        Expiration::AtHeight(height) => (b'h', height),
+        Expiration::AtTime(time) => (b't', time),
+        Expiration::Never {} => return None,
+    };
+    let mut key = vec![kind];
+    key.extend_from_slice(&value.to_be_bytes());
+    Some(key)
+}
+
+/// This returns the expiry index key of the clawback with the given id and end time / height
+pub fn expiry_key(end_time: &Expiration, id: &[u8]) -> Option<Vec<u8>> {
+    expiry_prefix(end_time).map(|mut key| {
+        key.extend_from_slice(id);
+        key
+    })
+}
+
+fn parse_expiry_key(key: &[u8]) -> StdResult<(String, Expiration)> {
+    if key.len() < 9 {
+        return Err(StdError::parse_err("expiry key", "too short"));
+    }
+    let mut value = [0u8; 8];
+    value.copy_from_slice(&key[1..9]);
+    let value = u64::from_be_bytes(value);
+    let end_time = match key[0] {
+        b'h' => Expiration::AtHeight(value),
+        b't' => Expiration::AtTime(value),
+        _ => return Err(StdError::parse_err("expiry key", "unknown kind")),
+    };
+    let id = String::from_utf8(key[9..].to_vec())
+        .map_err(|_| StdError::invalid_utf8("parsing clawback key"))?;
+    Ok((id, end_time))
+}
+
+/// This returns up to limit ids (with their end time / height) of clawbacks
+/// in the expiry index between the start (inclusive) and end (exclusive) keys
+pub fn expiry_index_range<S: ReadonlyStorage>(
+    storage: &S,
+    start: &[u8],
+    end: &[u8],
+    limit: usize,
+) -> StdResult<Vec<(String, Expiration)>> {
+    expiry_index_read(storage)
+        .range(Some(start), Some(end), Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (k, _) = item?;
+            parse_expiry_key(&k)
+        })
+        .collect()
+}
+
+fn index_clawback<S: Storage>(storage: &mut S, id: &[u8], clawback: &Clawback) -> StdResult<()> {
+    address_index(storage, PREFIX_HOLDER_INDEX, &clawback.holder).save(id, &Empty {})?;
+    address_index(storage, PREFIX_BACKUP_INDEX, &clawback.backup).save(id, &Empty {})?;
+    if let Some(key) = expiry_key(&clawback.end_time, id) {
+        expiry_index(storage).save(&key, &Empty {})?;
+    }
+    Ok(())
+}
+
+fn unindex_clawback<S: Storage>(storage: &mut S, id: &[u8], clawback: &Clawback) {
+    address_index(storage, PREFIX_HOLDER_INDEX, &clawback.holder).remove(id);
+    address_index(storage, PREFIX_BACKUP_INDEX, &clawback.backup).remove(id);
+    if let Some(key) = expiry_key(&clawback.end_time, id) {
+        expiry_index(storage).remove(&key);
+    }
+}
+
+/// This saves the clawback under the given id and updates the total locked balance
+/// with the difference from the previously stored clawback (if any),
+/// as well as the holder, backup and expiry indexes.
+/// All handlers should use this instead of saving into `clawbacks` directly.
+pub fn save_clawback<S: Storage>(storage: &mut S, id: &[u8], clawback: &Clawback) -> StdResult<()> {
+    let mut total = total_locked_read(storage).may_load()?.unwrap_or_default();
+    if let Some(old) = clawbacks_read(storage).may_load(id)? {
+        total
+            .remove_balance(&old.balance)
+            .map_err(|_| StdError::generic_err("total locked balance underflow"))?;
+        unindex_clawback(storage, id, &old);
+    }
+    total.add_balance(&clawback.balance);
+    total_locked(storage).save(&total)?;
+    index_clawback(storage, id, clawback)?;
+    clawbacks(storage).save(id, clawback)
+}
+
+/// This removes the clawback under the given id from storage and the indexes
+/// and subtracts its balance from the total locked balance
+/// (the caller is expected to send the funds out).
+/// Its open proposals are removed as well, as they can never pass.
+pub fn remove_clawback<S: Storage>(storage: &mut S, id: &[u8]) -> StdResult<()> {
+    let old = clawbacks_read(storage).load(id)?;
+    let mut total = total_locked_read(storage).may_load()?.unwrap_or_default();
+    total
+        .remove_balance(&old.balance)
+        .map_err(|_| StdError::generic_err("total locked balance underflow"))?;
+    total_locked(storage).save(&total)?;
+    unindex_clawback(storage, id, &old);
+    clawbacks(storage).remove(id);
+    for proposal_id in clawback_proposal_ids(storage, id)? {
+        remove_proposal(storage, proposal_id, id);
+    }
+    Ok(())
+}
+
+/// Clawback as stored by the 0.3.x versions (only time-based periods)
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ClawbackV0_3 {
+    pub backup: CanonicalAddr,
+    pub holder: CanonicalAddr,
+    pub end_time: u64,
+    pub clawback_period: u64,
+    pub balance: GenericBalance,
+    pub cw20_whitelist: Vec<CanonicalAddr>,
+}
+
+impl From<ClawbackV0_3> for Clawback {
+    fn from(old: ClawbackV0_3) -> Clawback {
+        Clawback {
+            backup: old.backup,
+            holder: old.holder,
+            end_time: Expiration::AtTime(old.end_time),
+            clawback_period: Duration::Time(old.clawback_period),
+            balance: old.balance,
+            cw20_whitelist: old.cw20_whitelist,
+            backup_multisig: None,
+            pending_backup: None,
+            refresh_policy: RefreshPolicy::default(),
+            max_end_time: None,
+            refreshes: 0,
+            release: ReleaseSchedule::default(),
+            withdrawn: GenericBalance::default(),
+            taken_back: GenericBalance::default(),
+            released: GenericBalance::default(),
+            max_clawback: None,
+            clawed_back: GenericBalance::default(),
+            arbiter: None,
+            dispute: None,
+            depositors: Depositors::default(),
+            native_whitelist: None,
+            unlisted_denoms: UnlistedDenoms::default(),
+        }
+    }
+}
+
+/// This rewrites up to limit clawbacks stored in the 0.3.x layout after start_after
+/// (end_time and clawback_period in seconds) to time-based expirations and durations,
+/// and adds them to the indexes and the total locked balance (which 0.3.x did not keep).
+/// Returns the key of the last converted clawback if there are more to convert.
+pub fn migrate_clawbacks_v0_3<S: Storage>(
+    storage: &mut S,
+    start_after: Option<Vec<u8>>,
+    limit: usize,
+) -> StdResult<Option<Vec<u8>>> {
+    // the clawbacks up to start_after are in the new layout already
+    let start = start_after.map(|mut key| {
+        key.push(0);
+        key
+    });
+    let mut old: Vec<(Vec<u8>, ClawbackV0_3)> = bucket_read(storage, PREFIX_CLAWBACK)
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit + 1)
+        .collect::<StdResult<_>>()?;
+    let more = old.len() > limit;
+    old.truncate(limit);
+
+    let mut total = total_locked_read(storage).may_load()?.unwrap_or_default();
+    for (id, clawback) in old.iter() {
+        let clawback: Clawback = clawback.clone().into();
+        total.add_balance(&clawback.balance);
+        index_clawback(storage, id, &clawback)?;
+        clawbacks(storage).save(id, &clawback)?;
+    }
+    total_locked(storage).save(&total)?;
+    Ok(match old.pop() {
+        Some((id, _)) if more => Some(id),
+        _ => None,
+    })
+}
+
+/// This assigns a new id, skipping any id already chosen by a user
+pub fn next_clawback_id<S: Storage>(storage: &mut S) -> StdResult<String> {
+    let mut counter = id_counter_read(storage).may_load()?.unwrap_or_default();
+    let id = loop {
+        counter += 1;
+        let id = format!("clawback-{}", counter);
+        if clawbacks_read(storage).may_load(id.as_bytes())?.is_none() {
+            break id;
+        }
+    };
+    id_counter(storage).save(&counter)?;
+    Ok(id)
+}
+
 /// This returns the list of ids for all registered clawbacks
 pub fn all_clawback_ids<S: ReadonlyStorage>(storage: &S) -> StdResult<Vec<String>> {
     prefixed_read(storage, PREFIX_CLAWBACK)
@@ -159,6 +1076,41 @@ pub fn all_clawback_ids<S: ReadonlyStorage>(storage: &S) -> StdResult<Vec<String
         .collect()
 }
 
+/// This returns up to limit ids of registered clawbacks after start_after
+pub fn clawback_ids<S: ReadonlyStorage>(
+    storage: &S,
+    start_after: Option<String>,
+    limit: usize,
+) -> StdResult<Vec<String>> {
+    let start = calc_range_start_string(start_after);
+    prefixed_read(storage, PREFIX_CLAWBACK)
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|(k, _)| {
+            String::from_utf8(k).map_err(|_| StdError::invalid_utf8("parsing clawback key"))
+        })
+        .collect()
+}
+
+/// This returns up to limit registered clawbacks (with their ids) after start_after
+pub fn range_clawbacks<S: ReadonlyStorage>(
+    storage: &S,
+    start_after: Option<String>,
+    limit: usize,
+) -> StdResult<Vec<(String, Clawback)>> {
+    let start = calc_range_start_string(start_after);
+    clawbacks_read(storage)
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (k, clawback) = item?;
+            let id =
+                String::from_utf8(k).map_err(|_| StdError::invalid_utf8("parsing clawback key"))?;
+            Ok((id, clawback))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -177,7 +1129,26 @@ mod tests {
         Clawback {
             holder: CanonicalAddr(Binary(b"hold".to_vec())),
             backup: CanonicalAddr(Binary(b"back".to_vec())),
-            ..Clawback::default()
+            end_time: Expiration::AtTime(1000),
+            clawback_period: Duration::Time(100),
+            balance: GenericBalance::default(),
+            cw20_whitelist: vec![],
+            backup_multisig: None,
+            pending_backup: None,
+            refresh_policy: RefreshPolicy::default(),
+            max_end_time: None,
+            refreshes: 0,
+            release: ReleaseSchedule::default(),
+            withdrawn: GenericBalance::default(),
+            taken_back: GenericBalance::default(),
+            released: GenericBalance::default(),
+            max_clawback: None,
+            clawed_back: GenericBalance::default(),
+            arbiter: None,
+            dispute: None,
+            depositors: Depositors::default(),
+            native_whitelist: None,
+            unlisted_denoms: UnlistedDenoms::default(),
         }
     }
 
@@ -201,4 +1172,249 @@ mod tests {
             ids
         )
     }
+
+    #[test]
+    fn clawback_ids_paginated() {
+        let mut storage = MockStorage::new();
+        for id in ["lazy", "assign", "zen", "lazy-dog"].iter() {
+            clawbacks(&mut storage)
+                .save(id.as_bytes(), &dummy_clawback())
+                .unwrap();
+        }
+
+        let ids = clawback_ids(&storage, None, 2).unwrap();
+        assert_eq!(vec!["assign".to_string(), "lazy".to_string()], ids);
+        let ids = clawback_ids(&storage, Some("lazy".to_string()), 2).unwrap();
+        assert_eq!(vec!["lazy-dog".to_string(), "zen".to_string()], ids);
+        let ids = clawback_ids(&storage, Some("zen".to_string()), 2).unwrap();
+        assert_eq!(0, ids.len());
+
+        let items = range_clawbacks(&storage, Some("lazy".to_string()), 1).unwrap();
+        assert_eq!(vec![("lazy-dog".to_string(), dummy_clawback())], items);
+    }
+
+    #[test]
+    fn save_and_remove_clawback_update_indexes() {
+        let mut storage = MockStorage::new();
+        let holder = CanonicalAddr(Binary(b"hold".to_vec()));
+        let backup = CanonicalAddr(Binary(b"back".to_vec()));
+        let other = CanonicalAddr(Binary(b"other".to_vec()));
+        save_clawback(&mut storage, b"first", &dummy_clawback()).unwrap();
+        save_clawback(&mut storage, b"second", &dummy_clawback()).unwrap();
+        let ids = address_index_ids(&storage, PREFIX_HOLDER_INDEX, &holder, None, 10).unwrap();
+        assert_eq!(vec!["first".to_string(), "second".to_string()], ids);
+        let ids = address_index_ids(&storage, PREFIX_BACKUP_INDEX, &backup, None, 10).unwrap();
+        assert_eq!(vec!["first".to_string(), "second".to_string()], ids);
+        let ids = address_index_ids(
+            &storage,
+            PREFIX_BACKUP_INDEX,
+            &backup,
+            Some("first".to_string()),
+            10,
+        )
+        .unwrap();
+        assert_eq!(vec!["second".to_string()], ids);
+
+        // a new holder moves the clawback in the index
+        let clawback = Clawback {
+            holder: other.clone(),
+            ..dummy_clawback()
+        };
+        save_clawback(&mut storage, b"first", &clawback).unwrap();
+        let ids = address_index_ids(&storage, PREFIX_HOLDER_INDEX, &holder, None, 10).unwrap();
+        assert_eq!(vec!["second".to_string()], ids);
+        let ids = address_index_ids(&storage, PREFIX_HOLDER_INDEX, &other, None, 10).unwrap();
+        assert_eq!(vec!["first".to_string()], ids);
+
+        // removed clawbacks are no longer indexed
+        remove_clawback(&mut storage, b"second").unwrap();
+        let ids = address_index_ids(&storage, PREFIX_HOLDER_INDEX, &holder, None, 10).unwrap();
+        assert_eq!(0, ids.len());
+        let ids = address_index_ids(&storage, PREFIX_BACKUP_INDEX, &backup, None, 10).unwrap();
+        assert_eq!(vec!["first".to_string()], ids);
+    }
+
+    #[test]
+    fn expiry_index_ordered() {
+        let mut storage = MockStorage::new();
+        for (id, end_time) in [
+            ("late", Expiration::AtTime(300)),
+            ("early", Expiration::AtTime(5)),
+            ("height", Expiration::AtHeight(7)),
+            ("middle", Expiration::AtTime(256)),
+        ]
+        .iter()
+        {
+            let clawback = Clawback {
+                end_time: *end_time,
+                ..dummy_clawback()
+            };
+            save_clawback(&mut storage, id.as_bytes(), &clawback).unwrap();
+        }
+
+        let start = expiry_prefix(&Expiration::AtTime(0)).unwrap();
+        let end = expiry_prefix(&Expiration::AtTime(300)).unwrap();
+        let items = expiry_index_range(&storage, &start, &end, 10).unwrap();
+        assert_eq!(
+            items,
+            vec![
+                ("early".to_string(), Expiration::AtTime(5)),
+                ("middle".to_string(), Expiration::AtTime(256)),
+            ]
+        );
+
+        // refreshing moves the clawback in the index
+        let clawback = Clawback {
+            end_time: Expiration::AtTime(1),
+            ..dummy_clawback()
+        };
+        save_clawback(&mut storage, b"late", &clawback).unwrap();
+        let items = expiry_index_range(&storage, &start, &end, 1).unwrap();
+        assert_eq!(items, vec![("late".to_string(), Expiration::AtTime(1))]);
+
+        let start = expiry_prefix(&Expiration::AtHeight(0)).unwrap();
+        let end = expiry_prefix(&Expiration::AtHeight(8)).unwrap();
+        remove_clawback(&mut storage, b"height").unwrap();
+        let items = expiry_index_range(&storage, &start, &end, 10).unwrap();
+        assert_eq!(0, items.len());
+    }
+
+    #[test]
+    fn save_and_remove_clawback_track_total_locked() {
+        let mut storage = MockStorage::new();
+        let mut clawback = dummy_clawback();
+        clawback
+            .balance
+            .add_tokens(Balance::from(vec![Coin::new(100, "atom")]));
+        save_clawback(&mut storage, b"first", &clawback).unwrap();
+        save_clawback(&mut storage, b"second", &clawback).unwrap();
+        let total = total_locked_read(&storage).load().unwrap();
+        assert_eq!(total.native, vec![Coin::new(200, "atom")]);
+
+        // overwriting only accounts for the difference
+        clawback
+            .balance
+            .add_tokens(Balance::from(vec![Coin::new(5, "eth")]));
+        save_clawback(&mut storage, b"first", &clawback).unwrap();
+        let total = total_locked_read(&storage).load().unwrap();
+        assert_eq!(
+            total.native,
+            vec![Coin::new(200, "atom"), Coin::new(5, "eth")]
+        );
+
+        remove_clawback(&mut storage, b"first").unwrap();
+        let total = total_locked_read(&storage).load().unwrap();
+        assert_eq!(total.native, vec![Coin::new(100, "atom")]);
+        assert_eq!(
+            vec!["second".to_string()],
+            all_clawback_ids(&storage).unwrap()
+        );
+    }
+
+    #[test]
+    fn proposals_paginated() {
+        let mut storage = MockStorage::new();
+        for id in [1, 2, u64::MAX].iter() {
+            let proposal = Proposal {
+                clawback_id: "cold".to_string(),
+                action: BackupAction::Burn {},
+                votes: vec![],
+                expires: Expiration::AtTime(1000),
+            };
+            save_proposal(&mut storage, *id, &proposal).unwrap();
+        }
+        let ids = |start_after: Option<u64>| -> Vec<u64> {
+            range_proposals(&storage, start_after, 10)
+                .unwrap()
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect()
+        };
+        assert_eq!(ids(None), vec![1, 2, u64::MAX]);
+        assert_eq!(ids(Some(1)), vec![2, u64::MAX]);
+        assert_eq!(ids(Some(u64::MAX)), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn withdrawable_large_amounts() {
+        let balance = GenericBalance {
+            native: vec![Coin::new(100_000_000_000_000_000_000, "atom")],
+            cw20: vec![],
+        };
+        let none = GenericBalance::default();
+        let steps = u64::MAX as u128;
+        let released = balance.withdrawable(&none, &none, steps - 1, steps);
+        assert_eq!(
+            released.native,
+            vec![Coin::new(99_999_999_999_999_999_994, "atom")]
+        );
+        let released = balance.withdrawable(&none, &none, 1, steps);
+        assert_eq!(released.native, vec![Coin::new(5, "atom")]);
+    }
+
+    #[test]
+    fn migrate_v0_3_layout() {
+        let mut storage = MockStorage::new();
+        let old = ClawbackV0_3 {
+            holder: CanonicalAddr(Binary(b"hold".to_vec())),
+            backup: CanonicalAddr(Binary(b"back".to_vec())),
+            end_time: 1000,
+            clawback_period: 100,
+            balance: GenericBalance {
+                native: vec![Coin::new(100, "atom")],
+                cw20: vec![],
+            },
+            cw20_whitelist: vec![],
+        };
+        bucket(&mut storage, PREFIX_CLAWBACK)
+            .save(b"old", &old)
+            .unwrap();
+        // the new layout cannot be read before the migration
+        assert!(clawbacks_read(&storage).load(b"old").is_err());
+
+        let last = migrate_clawbacks_v0_3(&mut storage, None, 10).unwrap();
+        assert_eq!(last, None);
+        let clawback = clawbacks_read(&storage).load(b"old").unwrap();
+        assert_eq!(clawback.end_time, Expiration::AtTime(1000));
+        assert_eq!(clawback.clawback_period, Duration::Time(100));
+        assert_eq!(clawback.balance, old.balance);
+    }
+
+    #[test]
+    fn migrate_v0_3_in_batches() {
+        let mut storage = MockStorage::new();
+        let ids: [&[u8]; 3] = [b"aaa", b"bbb", b"ccc"];
+        for id in ids.iter() {
+            let old = ClawbackV0_3 {
+                holder: CanonicalAddr(Binary(b"hold".to_vec())),
+                backup: CanonicalAddr(Binary(b"back".to_vec())),
+                end_time: 1000,
+                clawback_period: 100,
+                balance: GenericBalance {
+                    native: vec![Coin::new(100, "atom")],
+                    cw20: vec![],
+                },
+                cw20_whitelist: vec![],
+            };
+            bucket(&mut storage, PREFIX_CLAWBACK)
+                .save(id, &old)
+                .unwrap();
+        }
+
+        // each batch goes on after the last one
+        let last = migrate_clawbacks_v0_3(&mut storage, None, 2).unwrap();
+        assert_eq!(last, Some(b"bbb".to_vec()));
+        assert!(clawbacks_read(&storage).load(b"bbb").is_ok());
+        assert!(clawbacks_read(&storage).load(b"ccc").is_err());
+        let last = migrate_clawbacks_v0_3(&mut storage, last, 2).unwrap();
+        assert_eq!(last, None);
+        assert!(clawbacks_read(&storage).load(b"ccc").is_ok());
+
+        let total = total_locked_read(&storage).load().unwrap();
+        assert_eq!(total.native, vec![Coin::new(300, "atom")]);
+        let holder = CanonicalAddr(Binary(b"hold".to_vec()));
+        let by_holder =
+            address_index_ids(&storage, PREFIX_HOLDER_INDEX, &holder, None, 10).unwrap();
+        assert_eq!(by_holder.len(), 3);
+    }
 }